@@ -0,0 +1,28 @@
+// The final scene of Ray Tracing in One Weekend, built and rendered with the
+// library rather than through the binary. Run with
+// `cargo run --release --example random_spheres`.
+
+use std::path::Path;
+
+use rtow_rust::engine::output::{ write_image, ImageFormat };
+use rtow_rust::primitive::bvh::BvhNode;
+use rtow_rust::primitive::hittable_list::HittableList;
+use rtow_rust::presets;
+
+// SEEDING
+const RENDER_SEED: u64 = 0;
+
+// OUTPUT
+const OUTPUT_PATH: &str = "image.png";
+
+fn main() {
+    let mut scene = presets::random_spheres();
+    let world = HittableList::build(BvhNode::from(scene.world));
+
+    scene.camera.set_seed(RENDER_SEED);
+    scene.camera.initialize();
+    let framebuffer = scene.camera.render(&world, &scene.lights);
+
+    let output_path = Path::new(OUTPUT_PATH);
+    write_image(&framebuffer, output_path, ImageFormat::Png).expect("Failed to write the rendered image.");
+}
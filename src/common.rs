@@ -4,8 +4,11 @@ pub mod colour;
 pub mod ray;
 pub mod vec3;
 pub mod mat3;
+pub mod mat4;
+pub mod quaternion;
 pub mod interval;
 pub mod random;
+pub mod noise;
 
 // Important constants.
 
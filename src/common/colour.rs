@@ -14,7 +14,8 @@ fn linear_to_gamma(linear_component: f64) -> f64 {
     }
 }
 
-pub fn write_colour(pixel_colour: & Colour) {
+// Gamma-encodes a linear colour and quantises it to 8 bits per channel.
+pub fn colour_to_bytes(pixel_colour: & Colour) -> [u8; 3] {
     // retrieve the RGB values.
     let mut r = pixel_colour.x();
     let mut g = pixel_colour.y();
@@ -28,9 +29,14 @@ pub fn write_colour(pixel_colour: & Colour) {
 
     // [0, 1] => [0, 255]
     let intensity = Interval::build(0.000, 0.999);
-    let rbyte = (256.0 * intensity.clamp(r)) as i32;
-    let gbyte = (256.0 * intensity.clamp(g)) as i32;
-    let bbyte = (256.0 * intensity.clamp(b)) as i32;
+    let rbyte = (256.0 * intensity.clamp(r)) as u8;
+    let gbyte = (256.0 * intensity.clamp(g)) as u8;
+    let bbyte = (256.0 * intensity.clamp(b)) as u8;
 
-    println!("{} {} {}", rbyte, gbyte, bbyte);
+    [rbyte, gbyte, bbyte]
+}
+
+// Perceived brightness of a linear colour (Rec. 709 weights).
+pub fn luminance(colour: & Colour) -> f64 {
+    0.2126 * colour.x() + 0.7152 * colour.y() + 0.0722 * colour.z()
 }
@@ -1,3 +1,5 @@
+#[derive(Debug)]
+#[derive(Clone, Copy)]
 pub struct Interval {
     min: f64, 
     max: f64
@@ -18,6 +20,13 @@ impl Interval {
         }
     }
 
+    pub fn enclosing(a: & Interval, b: & Interval) -> Interval {
+        Interval {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max)
+        }
+    }
+
     pub fn size(&self) -> f64 {
         return self.max - self.min;
     }
@@ -45,7 +54,18 @@ impl Interval {
             _ => x
         }
     }
+
+    pub fn expand(&self, delta: f64) -> Interval {
+        let padding = delta / 2.0;
+        Interval::build(self.min - padding, self.max + padding)
+    }
+}
+
+impl Default for Interval {
+    fn default() -> Interval {
+        Interval::new()
+    }
 }
 
-const EMPTY: Interval = Interval{min: f64::INFINITY, max: f64::NEG_INFINITY};
-const UNIVERSE: Interval = Interval{min: f64::NEG_INFINITY, max: f64::INFINITY};
\ No newline at end of file
+pub const EMPTY: Interval = Interval{min: f64::INFINITY, max: f64::NEG_INFINITY};
+pub const UNIVERSE: Interval = Interval{min: f64::NEG_INFINITY, max: f64::INFINITY};
\ No newline at end of file
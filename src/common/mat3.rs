@@ -1,6 +1,10 @@
 use std::ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign};
 
-use super::vec3::{dot_product, Vector3};
+use super::degrees_to_radians;
+use super::vec3::{cross_product, dot_product, unit_vector, Point3, Vector3};
+
+// Below this a determinant counts as zero, and the matrix as singular.
+const SINGULAR_CUTOFF: f64 = 1e-12;
 
 #[derive(Debug)]
 #[derive(Clone, Copy)]
@@ -47,6 +51,48 @@ impl Matrix3 {
         }
     }
 
+    pub fn identity() -> Matrix3 {
+        Matrix3::build_explicit((1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (0.0, 0.0, 1.0))
+    }
+
+    // Rotates anticlockwise about `axis` (which needn't be unit length), looking
+    // down the axis towards the origin (Rodrigues' formula).
+    pub fn rotation(axis: & Vector3, degrees: f64) -> Matrix3 {
+        let (sin, cos) = degrees_to_radians(degrees).sin_cos();
+        let a = unit_vector(axis);
+        let (x, y, z) = (a.x(), a.y(), a.z());
+        let c = 1.0 - cos;
+
+        Matrix3::build_explicit(
+            (cos + x * x * c, x * y * c - z * sin, x * z * c + y * sin),
+            (y * x * c + z * sin, cos + y * y * c, y * z * c - x * sin),
+            (z * x * c - y * sin, z * y * c + x * sin, cos + z * z * c)
+        )
+    }
+
+    // The camera frame looking from `from` towards `at`: rows u (right), v (up)
+    // and w, which points back from `at` to `from`, as the camera uses them.
+    pub fn look_at(from: & Point3, at: & Point3, up: & Vector3) -> Matrix3 {
+        let w = unit_vector(&(from - at));
+        let u = unit_vector(&cross_product(up, &w));
+        let v = cross_product(&w, &u);
+        Matrix3::from(u, v, w)
+    }
+
+    // Rows u, v, w of some right-handed orthonormal frame around the unit vector w
+    // (Duff et al., "Building an Orthonormal Basis, Revisited").
+    pub fn orthonormal_basis(w: & Vector3) -> Matrix3 {
+        let sign = 1.0_f64.copysign(w.z());
+        let a = -1.0 / (sign + w.z());
+        let b = w.x() * w.y() * a;
+
+        Matrix3::from(
+            Vector3::build(1.0 + sign * w.x() * w.x() * a, sign * b, -sign * w.x()),
+            Vector3::build(b, sign + w.y() * w.y() * a, -w.y()),
+            *w
+        )
+    }
+
 
     // Struct functions.
     pub fn u(& self) -> & Vector3 {
@@ -61,6 +107,45 @@ impl Matrix3 {
         &self.data[2]
     }
 
+    // Takes coordinates along the rows u, v, w back to the standard frame.
+    pub fn to_world(& self, local: & Vector3) -> Vector3 {
+        self.data[0] * local.x() + self.data[1] * local.y() + self.data[2] * local.z()
+    }
+
+    pub fn column(& self, n: usize) -> Vector3 {
+        Vector3::build(self.data[0][n], self.data[1][n], self.data[2][n])
+    }
+
+    pub fn transpose(& self) -> Matrix3 {
+        Matrix3::from(self.column(0), self.column(1), self.column(2))
+    }
+
+    pub fn determinant(& self) -> f64 {
+        dot_product(self.u(), &cross_product(self.v(), self.w()))
+    }
+
+    // The adjugate over the determinant, whose columns are cross products of the
+    // rows. None if the matrix is singular.
+    pub fn inverse(& self) -> Option<Matrix3> {
+        let determinant = self.determinant();
+        if determinant.abs() < SINGULAR_CUTOFF {
+            return None
+        }
+
+        let columns = Matrix3::from(
+            cross_product(self.v(), self.w()),
+            cross_product(self.w(), self.u()),
+            cross_product(self.u(), self.v())
+        );
+        Some(columns.transpose() * (1.0 / determinant))
+    }
+
+}
+
+impl Default for Matrix3 {
+    fn default() -> Matrix3 {
+        Matrix3::new()
+    }
 }
 
 
@@ -181,7 +266,24 @@ impl AddAssign<& Matrix3> for Matrix3 {
 
 // Multiplication Operators
 
-// TODO: define matrix multiplication
+// 3x3 * 3x3 = 3x3
+impl Mul for & Matrix3 {
+    type Output = Matrix3;
+
+    fn mul(self, rhs: Self) -> Matrix3 {
+        Matrix3 {
+            data: [self.u() * rhs, self.v() * rhs, self.w() * rhs]
+        }
+    }
+}
+
+impl Mul for Matrix3 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Mul::mul(&self, &rhs)
+    }
+}
 
 // 3x3 * 3x1 = 3x1
 impl Mul<Vector3> for Matrix3 {
@@ -200,7 +302,11 @@ impl Mul<& Vector3> for & Matrix3 {
     type Output = Vector3;
 
     fn mul(self, rhs: & Vector3) -> Vector3 {
-        self.u() * rhs + self.v() * rhs + self.w() * rhs
+        Vector3::build(
+            dot_product(self.u(), rhs),
+            dot_product(self.v(), rhs),
+            dot_product(self.w(), rhs)
+        )
     }
 }
 
@@ -261,4 +367,58 @@ impl MulAssign<f64> for Matrix3 {
         self[1] *= &rhs;
         self[2] *= &rhs;
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: & Matrix3, b: & Matrix3) {
+        for row in 0..3 {
+            assert!((a[row] - b[row]).length() < 1e-9, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_the_matrix() {
+        let matrix = Matrix3::build_explicit((2.0, 1.0, 0.0), (0.0, 3.0, 1.0), (1.0, 0.0, 4.0));
+        let inverse = matrix.inverse().expect("matrix is invertible");
+
+        assert_close(&(inverse * matrix), &Matrix3::identity());
+        assert_close(&(matrix * inverse), &Matrix3::identity());
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        let matrix = Matrix3::build_explicit((1.0, 2.0, 3.0), (2.0, 4.0, 6.0), (0.0, 1.0, 0.0));
+        assert!(matrix.inverse().is_none());
+    }
+
+    #[test]
+    fn transpose_swaps_rows_and_columns() {
+        let matrix = Matrix3::build_explicit((1.0, 2.0, 3.0), (4.0, 5.0, 6.0), (7.0, 8.0, 9.0));
+        let transpose = matrix.transpose();
+
+        assert_close(&transpose, &Matrix3::build_explicit((1.0, 4.0, 7.0), (2.0, 5.0, 8.0), (3.0, 6.0, 9.0)));
+        assert_close(&transpose.transpose(), &matrix);
+    }
+
+    #[test]
+    fn look_at_builds_the_camera_frame() {
+        let frame = Matrix3::look_at(&Point3::build(0.0, 0.0, 5.0), &Point3::new(), &Vector3::build(0.0, 1.0, 0.0));
+        assert_close(&frame, &Matrix3::identity());
+
+        // Any other view still gives an orthonormal frame with w pointing back at the eye.
+        let (from, at) = (Point3::build(3.0, 2.0, -1.0), Point3::build(-1.0, 0.5, 2.0));
+        let frame = Matrix3::look_at(&from, &at, &Vector3::build(0.0, 1.0, 0.0));
+        assert_close(&(frame * frame.transpose()), &Matrix3::identity());
+        assert!((*frame.w() - unit_vector(&(from - at))).length() < 1e-9);
+    }
+
+    #[test]
+    fn rotation_turns_anticlockwise() {
+        let rotation = Matrix3::rotation(&Vector3::build(0.0, 0.0, 2.0), 90.0);
+        let turned = rotation * Vector3::build(1.0, 0.0, 0.0);
+        assert!((turned - Vector3::build(0.0, 1.0, 0.0)).length() < 1e-9);
+    }
+}
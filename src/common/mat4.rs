@@ -0,0 +1,315 @@
+use std::ops::{ Index, IndexMut, Mul };
+
+use super::degrees_to_radians;
+use super::mat3::Matrix3;
+use super::quaternion::Quaternion;
+use super::vec3::{ dot_product, Point3, Vector3 };
+
+// Below this a pivot counts as zero, and the matrix as singular.
+const SINGULAR_CUTOFF: f64 = 1e-12;
+
+// A 4x4 matrix for affine transforms of points and vectors in homogeneous
+// coordinates. Like Matrix3 it is row-major, and it acts on column vectors,
+// so in `a * b` the transform b is applied first.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub struct Matrix4 {
+    data: [[f64; 4]; 4]
+}
+
+impl Matrix4 {
+    // Constructors
+    pub fn new() -> Matrix4 {
+        Matrix4 {
+            data: [[0.0; 4]; 4]
+        }
+    }
+
+    pub fn from(data: [[f64; 4]; 4]) -> Matrix4 {
+        Matrix4 { data }
+    }
+
+    pub fn identity() -> Matrix4 {
+        Matrix4::from([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ])
+    }
+
+    // Applies `linear` and then moves by `translation`.
+    pub fn affine(linear: & Matrix3, translation: & Vector3) -> Matrix4 {
+        let row = |r: usize, offset: f64| [linear[r][0], linear[r][1], linear[r][2], offset];
+        Matrix4::from([
+            row(0, translation.x()),
+            row(1, translation.y()),
+            row(2, translation.z()),
+            [0.0, 0.0, 0.0, 1.0]
+        ])
+    }
+
+    pub fn translation(offset: & Vector3) -> Matrix4 {
+        Matrix4::from([
+            [1.0, 0.0, 0.0, offset.x()],
+            [0.0, 1.0, 0.0, offset.y()],
+            [0.0, 0.0, 1.0, offset.z()],
+            [0.0, 0.0, 0.0, 1.0]
+        ])
+    }
+
+    pub fn scaling(factors: & Vector3) -> Matrix4 {
+        Matrix4::from([
+            [factors.x(), 0.0, 0.0, 0.0],
+            [0.0, factors.y(), 0.0, 0.0],
+            [0.0, 0.0, factors.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ])
+    }
+
+    // Rotations are anticlockwise looking down the axis towards the origin.
+    pub fn rotation_x(degrees: f64) -> Matrix4 {
+        let (sin, cos) = degrees_to_radians(degrees).sin_cos();
+        Matrix4::from([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, cos, -sin, 0.0],
+            [0.0, sin, cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ])
+    }
+
+    pub fn rotation_y(degrees: f64) -> Matrix4 {
+        let (sin, cos) = degrees_to_radians(degrees).sin_cos();
+        Matrix4::from([
+            [cos, 0.0, sin, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [-sin, 0.0, cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ])
+    }
+
+    pub fn rotation_z(degrees: f64) -> Matrix4 {
+        let (sin, cos) = degrees_to_radians(degrees).sin_cos();
+        Matrix4::from([
+            [cos, -sin, 0.0, 0.0],
+            [sin, cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ])
+    }
+
+
+    // About any axis through the origin; see Matrix3::rotation.
+    pub fn rotation(axis: & Vector3, degrees: f64) -> Matrix4 {
+        Matrix4::affine(&Matrix3::rotation(axis, degrees), &Vector3::new())
+    }
+
+    pub fn from_quaternion(rotation: & Quaternion) -> Matrix4 {
+        Matrix4::affine(&rotation.to_matrix(), &Vector3::new())
+    }
+
+    // The view transform of a camera at `from` looking at `at`: world space into
+    // a frame with the camera at the origin, x right, y up, looking down -z.
+    // Its inverse places an object at `from` facing `at` along its -z.
+    pub fn look_at(from: & Point3, at: & Point3, up: & Vector3) -> Matrix4 {
+        let frame = Matrix3::look_at(from, at, up);
+        let translation = Vector3::build(
+            -dot_product(frame.u(), from),
+            -dot_product(frame.v(), from),
+            -dot_product(frame.w(), from)
+        );
+        Matrix4::affine(&frame, &translation)
+    }
+
+
+    // Struct functions.
+    // The upper left 3x3, which is everything but the translation for an affine transform.
+    pub fn linear(& self) -> Matrix3 {
+        let row = |r: usize| Vector3::build(self.data[r][0], self.data[r][1], self.data[r][2]);
+        Matrix3::from(row(0), row(1), row(2))
+    }
+
+    pub fn translation_part(& self) -> Vector3 {
+        Vector3::build(self.data[0][3], self.data[1][3], self.data[2][3])
+    }
+
+    // Cheaper and steadier than the general inverse when the bottom row is 0, 0, 0, 1.
+    pub fn affine_inverse(& self) -> Option<Matrix4> {
+        let linear = self.linear().inverse()?;
+        let translation = linear * self.translation_part();
+        Some(Matrix4::affine(&linear, &-translation))
+    }
+
+    pub fn transpose(& self) -> Matrix4 {
+        let mut result = Matrix4::new();
+        for row in 0..4 {
+            for column in 0..4 {
+                result.data[row][column] = self.data[column][row];
+            }
+        }
+        result
+    }
+
+    // Gauss-Jordan elimination with partial pivoting. None if the matrix is singular.
+    pub fn inverse(& self) -> Option<Matrix4> {
+        let mut left = self.data;
+        let mut right = Matrix4::identity().data;
+
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|&a, &b| left[a][column].abs().total_cmp(&left[b][column].abs()))
+                .expect("Matrix4: no rows left to pivot on.");
+            if left[pivot][column].abs() < SINGULAR_CUTOFF {
+                return None
+            }
+            left.swap(column, pivot);
+            right.swap(column, pivot);
+
+            let scale = 1.0 / left[column][column];
+            for k in 0..4 {
+                left[column][k] *= scale;
+                right[column][k] *= scale;
+            }
+
+            for row in 0..4 {
+                let factor = left[row][column];
+                if row == column || factor == 0.0 {
+                    continue
+                }
+                for k in 0..4 {
+                    left[row][k] -= factor * left[column][k];
+                    right[row][k] -= factor * right[column][k];
+                }
+            }
+        }
+
+        Some(Matrix4::from(right))
+    }
+
+    // Points are moved by the translation part.
+    pub fn transform_point(& self, point: & Point3) -> Point3 {
+        let row = |r: usize| {
+            self.data[r][0] * point.x() + self.data[r][1] * point.y() + self.data[r][2] * point.z() + self.data[r][3]
+        };
+        Point3::build(row(0), row(1), row(2))
+    }
+
+    // Directions are not.
+    pub fn transform_vector(& self, vector: & Vector3) -> Vector3 {
+        let row = |r: usize| {
+            self.data[r][0] * vector.x() + self.data[r][1] * vector.y() + self.data[r][2] * vector.z()
+        };
+        Vector3::build(row(0), row(1), row(2))
+    }
+}
+
+impl Default for Matrix4 {
+    fn default() -> Matrix4 {
+        Matrix4::new()
+    }
+}
+
+
+// Index Operators
+
+impl Index<usize> for Matrix4 {
+    type Output = [f64; 4];
+
+    fn index(&self, index: usize) -> & [f64; 4] {
+        self.data.get(index).expect("Matrix4: row index out of range.")
+    }
+}
+
+impl IndexMut<usize> for Matrix4 {
+    fn index_mut(&mut self, index: usize) -> &mut [f64; 4] {
+        self.data.get_mut(index).expect("Matrix4: row index out of range.")
+    }
+}
+
+
+// Multiplication Operators
+
+impl Mul for & Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: Self) -> Matrix4 {
+        let mut result = Matrix4::new();
+        for row in 0..4 {
+            for column in 0..4 {
+                result.data[row][column] = (0..4).map(|k| self.data[row][k] * rhs.data[k][column]).sum();
+            }
+        }
+        result
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: Self) -> Matrix4 {
+        Mul::mul(&self, &rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: & Matrix4, b: & Matrix4) {
+        for row in 0..4 {
+            for column in 0..4 {
+                assert!((a[row][column] - b[row][column]).abs() < 1e-9, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    fn assert_vectors_close(a: & Vector3, b: & Vector3) {
+        assert!((*a - *b).length() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    fn transform() -> Matrix4 {
+        Matrix4::translation(&Vector3::build(1.0, -2.0, 3.0))
+            * Matrix4::rotation(&Vector3::build(1.0, 1.0, 0.0), 30.0)
+            * Matrix4::scaling(&Vector3::build(2.0, 0.5, 1.5))
+    }
+
+    #[test]
+    fn inverse_undoes_the_matrix() {
+        let matrix = transform();
+        let inverse = matrix.inverse().expect("matrix is invertible");
+
+        assert_close(&(inverse * matrix), &Matrix4::identity());
+        assert_close(&(matrix * inverse), &Matrix4::identity());
+        assert_close(&matrix.affine_inverse().expect("matrix is invertible"), &inverse);
+        assert!(Matrix4::scaling(&Vector3::build(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+
+    #[test]
+    fn transpose_swaps_rows_and_columns() {
+        let matrix = transform();
+        let transpose = matrix.transpose();
+
+        assert_eq!(transpose[0][3], matrix[3][0]);
+        assert_eq!(transpose[2][1], matrix[1][2]);
+        assert_close(&transpose.transpose(), &matrix);
+    }
+
+    #[test]
+    fn points_are_translated_but_directions_are_not() {
+        let matrix = Matrix4::translation(&Vector3::build(1.0, 2.0, 3.0)) * Matrix4::rotation_z(90.0);
+        let x = Vector3::build(1.0, 0.0, 0.0);
+
+        assert_vectors_close(&matrix.transform_point(&x), &Point3::build(1.0, 3.0, 3.0));
+        assert_vectors_close(&matrix.transform_vector(&x), &Vector3::build(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn look_at_puts_the_camera_at_the_origin_looking_down_negative_z() {
+        let (from, at) = (Point3::build(3.0, 2.0, -1.0), Point3::build(-1.0, 0.5, 2.0));
+        let view = Matrix4::look_at(&from, &at, &Vector3::build(0.0, 1.0, 0.0));
+
+        assert_vectors_close(&view.transform_point(&from), &Point3::new());
+        let distance = (at - from).length();
+        assert_vectors_close(&view.transform_point(&at), &Point3::build(0.0, 0.0, -distance));
+    }
+}
@@ -0,0 +1,312 @@
+use super::random::Prng;
+use super::vec3::{ dot_product, random_unit_vector, Point3, Vector3 };
+
+const TABLE_SIZE: usize = 256;
+
+// Defaults for the fractal sums: each octave at double the frequency and half the amplitude.
+pub const DEFAULT_LACUNARITY: f64 = 2.0;
+pub const DEFAULT_GAIN: f64 = 0.5;
+
+// Smooth 3D noise, defined everywhere and repeating only every TABLE_SIZE units.
+pub trait Noise: Send + Sync {
+    fn noise(&self, point: & Point3) -> f64;
+}
+
+
+// Lattice hashing.
+
+// A shuffled 0..TABLE_SIZE, hashing integer lattice points to table indices.
+// The same seed always gives the same table, so noise built on it is repeatable.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct PermutationTable {
+    // Stored twice over so chained lookups never need wrapping.
+    values: Vec<usize>
+}
+
+impl PermutationTable {
+    pub fn build(rng: &mut Prng) -> PermutationTable {
+        // Fisher–Yates shuffle.
+        let mut permutation: Vec<usize> = (0..TABLE_SIZE).collect();
+        for i in (1..TABLE_SIZE).rev() {
+            let target = (rng.next_u64() % (i as u64 + 1)) as usize;
+            permutation.swap(i, target);
+        }
+
+        let values = permutation.iter().chain(permutation.iter()).copied().collect();
+        PermutationTable { values }
+    }
+
+    pub fn seed(seed: u64) -> PermutationTable {
+        PermutationTable::build(&mut Prng::seed(seed))
+    }
+
+    // Index in 0..TABLE_SIZE for the lattice point (x, y, z).
+    pub fn hash(&self, x: i64, y: i64, z: i64) -> usize {
+        let wrap = |coordinate: i64| (coordinate & (TABLE_SIZE as i64 - 1)) as usize;
+        self.values[self.values[self.values[wrap(x)] + wrap(y)] + wrap(z)]
+    }
+}
+
+
+// Gradient noise.
+
+// Perlin's gradient noise: random unit gradients on the integer lattice,
+// blended with a smoothed trilinear interpolation.
+pub struct Perlin {
+    gradients: Vec<Vector3>,
+    table: PermutationTable
+}
+
+impl Perlin {
+    pub fn build(rng: &mut Prng) -> Perlin {
+        let gradients = (0..TABLE_SIZE).map(|_| random_unit_vector(rng)).collect();
+
+        Perlin {
+            gradients,
+            table: PermutationTable::build(rng)
+        }
+    }
+
+    pub fn seed(seed: u64) -> Perlin {
+        Perlin::build(&mut Prng::seed(seed))
+    }
+}
+
+impl Noise for Perlin {
+    // Roughly in [-1, 1], and zero on every lattice point.
+    fn noise(&self, point: & Point3) -> f64 {
+        let floor = [point.x().floor(), point.y().floor(), point.z().floor()];
+        let fraction = [point.x() - floor[0], point.y() - floor[1], point.z() - floor[2]];
+        let cell = floor.map(|f| f as i64);
+
+        // Hermite smoothing hides the lattice.
+        let [u, v, w] = fraction.map(|f| f * f * (3.0 - 2.0 * f));
+
+        let mut accumulated = 0.0;
+        for i in 0..2 {
+            for j in 0..2 {
+                for k in 0..2 {
+                    let gradient = &self.gradients[self.table.hash(cell[0] + i, cell[1] + j, cell[2] + k)];
+                    let (i, j, k) = (i as f64, j as f64, k as f64);
+                    let offset = Vector3::build(fraction[0] - i, fraction[1] - j, fraction[2] - k);
+
+                    accumulated +=
+                        (i * u + (1.0 - i) * (1.0 - u)) *
+                        (j * v + (1.0 - j) * (1.0 - v)) *
+                        (k * w + (1.0 - k) * (1.0 - w)) *
+                        dot_product(gradient, &offset);
+                }
+            }
+        }
+
+        accumulated
+    }
+}
+
+
+// Cellular noise.
+
+// Worley's cellular noise: one feature point jittered inside every lattice
+// cell, measured by the distance to the nearest ones.
+pub struct Worley {
+    // Offsets of the feature points within their cells, each in [0, 1)^3.
+    features: Vec<Vector3>,
+    table: PermutationTable
+}
+
+impl Worley {
+    pub fn build(rng: &mut Prng) -> Worley {
+        let features = (0..TABLE_SIZE).map(|_| Vector3::random(rng, 0.0, 1.0)).collect();
+
+        Worley {
+            features,
+            table: PermutationTable::build(rng)
+        }
+    }
+
+    pub fn seed(seed: u64) -> Worley {
+        Worley::build(&mut Prng::seed(seed))
+    }
+
+    // Distances to the nearest and second nearest feature points (F1 and F2).
+    // F2 - F1 is close to zero along cell borders.
+    pub fn distances(&self, point: & Point3) -> (f64, f64) {
+        let cell = [point.x().floor() as i64, point.y().floor() as i64, point.z().floor() as i64];
+
+        let mut nearest = f64::INFINITY;
+        let mut second = f64::INFINITY;
+
+        // With one point per cell, the second nearest is never further than sqrt(3):
+        // the point's own cell and the face neighbour it is closest to each hold
+        // one that near. Cells two away can still beat that, but cells three away
+        // are at least 2 off, so the 5x5x5 neighbourhood always holds both.
+        for i in -2..=2 {
+            for j in -2..=2 {
+                for k in -2..=2 {
+                    let (x, y, z) = (cell[0] + i, cell[1] + j, cell[2] + k);
+                    let feature = Point3::build(x as f64, y as f64, z as f64) + self.features[self.table.hash(x, y, z)];
+                    let distance = (feature - *point).length_squared();
+
+                    if distance < nearest {
+                        second = nearest;
+                        nearest = distance;
+                    } else if distance < second {
+                        second = distance;
+                    }
+                }
+            }
+        }
+
+        (nearest.sqrt(), second.sqrt())
+    }
+}
+
+impl Noise for Worley {
+    // F1, from 0 at a feature point up to about 1.
+    fn noise(&self, point: & Point3) -> f64 {
+        self.distances(point).0
+    }
+}
+
+
+// Fractal sums.
+
+// Fractional Brownian motion: octaves of noise added at rising frequency
+// (times `lacunarity` each) and falling amplitude (times `gain` each).
+pub fn fbm(noise: & impl Noise, point: & Point3, octaves: usize, lacunarity: f64, gain: f64) -> f64 {
+    let mut accumulated = 0.0;
+    let mut sample_point = *point;
+    let mut weight = 1.0;
+
+    for _ in 0..octaves {
+        accumulated += weight * noise.noise(&sample_point);
+        weight *= gain;
+        sample_point = sample_point * lacunarity;
+    }
+
+    accumulated
+}
+
+// Like fbm, but summing |noise| so every zero crossing becomes a crease.
+pub fn turbulence(noise: & impl Noise, point: & Point3, octaves: usize, lacunarity: f64, gain: f64) -> f64 {
+    let mut accumulated = 0.0;
+    let mut sample_point = *point;
+    let mut weight = 1.0;
+
+    for _ in 0..octaves {
+        accumulated += weight * noise.noise(&sample_point).abs();
+        weight *= gain;
+        sample_point = sample_point * lacunarity;
+    }
+
+    accumulated
+}
+
+// Musgrave's ridged multifractal: inverted |noise| makes sharp ridges, and
+// each octave is weighted by the one before it, so detail piles up on the
+// ridges and valleys stay smooth. Roughly in [0, 2] with the default gain.
+pub fn ridged(noise: & impl Noise, point: & Point3, octaves: usize, lacunarity: f64, gain: f64) -> f64 {
+    // Shifts the ridges up; 1 puts their crests at exactly 1.
+    const OFFSET: f64 = 1.0;
+
+    let mut accumulated = 0.0;
+    let mut sample_point = *point;
+    let mut amplitude = 1.0;
+    let mut weight = 1.0;
+
+    for _ in 0..octaves {
+        let ridge = OFFSET - noise.noise(&sample_point).abs();
+        let signal = ridge * ridge * weight;
+
+        accumulated += signal * amplitude;
+        // Octaves only add detail where the previous ones were high.
+        weight = (signal * 2.0).clamp(0.0, 1.0);
+        amplitude *= gain;
+        sample_point = sample_point * lacunarity;
+    }
+
+    accumulated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_points(count: usize) -> Vec<Point3> {
+        let mut rng = Prng::seed(11);
+        (0..count).map(|_| Vector3::random(&mut rng, -20.0, 20.0)).collect()
+    }
+
+    #[test]
+    fn perlin_is_bounded_and_zero_on_the_lattice() {
+        let perlin = Perlin::seed(1);
+        for point in sample_points(10000) {
+            let value = perlin.noise(&point);
+            assert!((-1.0..=1.0).contains(&value), "noise at {:?} is {}", point, value);
+
+            let lattice = Point3::build(point.x().round(), point.y().round(), point.z().round());
+            assert!(perlin.noise(&lattice).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_noise() {
+        let points = sample_points(100);
+        let (perlin, again, other) = (Perlin::seed(4), Perlin::seed(4), Perlin::seed(5));
+        let (worley, worley_again, worley_other) = (Worley::seed(4), Worley::seed(4), Worley::seed(5));
+
+        assert!(points.iter().all(|point| perlin.noise(point) == again.noise(point)));
+        assert!(points.iter().all(|point| worley.noise(point) == worley_again.noise(point)));
+        assert!(points.iter().any(|point| perlin.noise(point) != other.noise(point)));
+        assert!(points.iter().any(|point| worley.noise(point) != worley_other.noise(point)));
+    }
+
+    // Against every feature point in a much wider neighbourhood.
+    #[test]
+    fn worley_finds_the_nearest_two_feature_points() {
+        let worley = Worley::seed(2);
+        // Random points rarely need more than the 3x3x3 neighbourhood, so add
+        // some where the second nearest is two cells away.
+        let far_second = [
+            Point3::build(-4.960722804135527, -4.989740663437253, 5.001742905388667),
+            Point3::build(-7.026265891561486, -14.219180018166666, -16.247819404088546),
+            Point3::build(-11.00419713377756, 9.74911880219905, 17.157710409942254),
+            Point3::build(-4.527930910634868, -3.401546509473377, -5.00640603320894)
+        ];
+        for point in sample_points(2000).into_iter().chain(far_second) {
+            let cell = [point.x().floor() as i64, point.y().floor() as i64, point.z().floor() as i64];
+            let mut distances = Vec::new();
+            for i in -4..=4 {
+                for j in -4..=4 {
+                    for k in -4..=4 {
+                        let (x, y, z) = (cell[0] + i, cell[1] + j, cell[2] + k);
+                        let feature = Point3::build(x as f64, y as f64, z as f64) + worley.features[worley.table.hash(x, y, z)];
+                        distances.push((feature - point).length());
+                    }
+                }
+            }
+            distances.sort_by(f64::total_cmp);
+
+            let (nearest, second) = worley.distances(&point);
+            assert!((nearest - distances[0]).abs() < 1e-12 && (second - distances[1]).abs() < 1e-12);
+            assert!(second <= 3.0_f64.sqrt());
+        }
+    }
+
+    #[test]
+    fn fbm_is_bounded_by_its_octave_weights() {
+        let perlin = Perlin::seed(3);
+        let octaves = 6;
+        let bound: f64 = (0..octaves).map(|octave| DEFAULT_GAIN.powi(octave)).sum();
+
+        for point in sample_points(2000) {
+            assert_eq!(fbm(&perlin, &point, 1, DEFAULT_LACUNARITY, DEFAULT_GAIN), perlin.noise(&point));
+
+            let value = fbm(&perlin, &point, octaves as usize, DEFAULT_LACUNARITY, DEFAULT_GAIN);
+            assert!(value.abs() <= bound, "fbm at {:?} is {}", point, value);
+            let rough = turbulence(&perlin, &point, octaves as usize, DEFAULT_LACUNARITY, DEFAULT_GAIN);
+            assert!((0.0..=bound).contains(&rough), "turbulence at {:?} is {}", point, rough);
+        }
+    }
+}
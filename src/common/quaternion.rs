@@ -0,0 +1,232 @@
+use std::ops::{ Add, Mul, Neg };
+
+use super::degrees_to_radians;
+use super::mat3::Matrix3;
+use super::vec3::{ cross_product, dot_product, unit_vector, Vector3 };
+
+// Past this cosine two rotations are close enough that slerp falls back to a
+// normalised lerp, which avoids dividing by a vanishing sine.
+const SLERP_LINEAR_CUTOFF: f64 = 0.9995;
+
+// w + xi + yj + zk, with `vector` holding x, y, z. Unit quaternions are
+// rotations; the others only turn up partway through arithmetic.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub struct Quaternion {
+    scalar: f64,
+    vector: Vector3
+}
+
+impl Quaternion {
+    // Constructors
+    pub fn new() -> Quaternion {
+        Quaternion {
+            scalar: 0.0,
+            vector: Vector3::new()
+        }
+    }
+
+    pub fn build(w: f64, x: f64, y: f64, z: f64) -> Quaternion {
+        Quaternion {
+            scalar: w,
+            vector: Vector3::build(x, y, z)
+        }
+    }
+
+    pub fn from(scalar: f64, vector: Vector3) -> Quaternion {
+        Quaternion { scalar, vector }
+    }
+
+    pub fn identity() -> Quaternion {
+        Quaternion::build(1.0, 0.0, 0.0, 0.0)
+    }
+
+    // Rotates anticlockwise about `axis`, looking down the axis towards the
+    // origin, the same way as Matrix3::rotation.
+    pub fn from_axis_angle(axis: & Vector3, degrees: f64) -> Quaternion {
+        let (sin, cos) = (degrees_to_radians(degrees) / 2.0).sin_cos();
+        Quaternion::from(cos, unit_vector(axis) * sin)
+    }
+
+    // The rotation a pure rotation matrix performs (Shepperd's method, which
+    // builds from the largest of the four components to stay accurate).
+    pub fn from_matrix(matrix: & Matrix3) -> Quaternion {
+        let m = |row: usize, column: usize| matrix[row][column];
+        let trace = m(0, 0) + m(1, 1) + m(2, 2);
+
+        let quaternion = if trace > 0.0 {
+            let s = 2.0 * (trace + 1.0).sqrt();
+            Quaternion::build(0.25 * s, (m(2, 1) - m(1, 2)) / s, (m(0, 2) - m(2, 0)) / s, (m(1, 0) - m(0, 1)) / s)
+        } else if m(0, 0) > m(1, 1) && m(0, 0) > m(2, 2) {
+            let s = 2.0 * (1.0 + m(0, 0) - m(1, 1) - m(2, 2)).sqrt();
+            Quaternion::build((m(2, 1) - m(1, 2)) / s, 0.25 * s, (m(0, 1) + m(1, 0)) / s, (m(0, 2) + m(2, 0)) / s)
+        } else if m(1, 1) > m(2, 2) {
+            let s = 2.0 * (1.0 + m(1, 1) - m(0, 0) - m(2, 2)).sqrt();
+            Quaternion::build((m(0, 2) - m(2, 0)) / s, (m(0, 1) + m(1, 0)) / s, 0.25 * s, (m(1, 2) + m(2, 1)) / s)
+        } else {
+            let s = 2.0 * (1.0 + m(2, 2) - m(0, 0) - m(1, 1)).sqrt();
+            Quaternion::build((m(1, 0) - m(0, 1)) / s, (m(0, 2) + m(2, 0)) / s, (m(1, 2) + m(2, 1)) / s, 0.25 * s)
+        };
+
+        quaternion.normalised()
+    }
+
+
+    // Struct functions.
+    pub fn scalar(& self) -> f64 {
+        self.scalar
+    }
+
+    pub fn vector(& self) -> & Vector3 {
+        &self.vector
+    }
+
+    pub fn dot(& self, other: & Quaternion) -> f64 {
+        self.scalar * other.scalar + dot_product(&self.vector, &other.vector)
+    }
+
+    pub fn length(& self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalised(& self) -> Quaternion {
+        *self * (1.0 / self.length())
+    }
+
+    // The inverse rotation, for unit quaternions.
+    pub fn conjugate(& self) -> Quaternion {
+        Quaternion::from(self.scalar, -self.vector)
+    }
+
+    // q v q*, expanded so no intermediate quaternions are built.
+    pub fn rotate(& self, vector: & Vector3) -> Vector3 {
+        let t = cross_product(&self.vector, vector) * 2.0;
+        vector + &(t * self.scalar) + cross_product(&self.vector, &t)
+    }
+
+    pub fn to_matrix(& self) -> Matrix3 {
+        let (w, x, y, z) = (self.scalar, self.vector.x(), self.vector.y(), self.vector.z());
+
+        Matrix3::build_explicit(
+            (1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y)),
+            (2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x)),
+            (2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y))
+        )
+    }
+}
+
+impl Default for Quaternion {
+    fn default() -> Quaternion {
+        Quaternion::new()
+    }
+}
+
+// Spherical linear interpolation between unit quaternions a (at t = 0) and
+// b (at t = 1), turning at a constant rate the short way round.
+pub fn slerp(a: & Quaternion, b: & Quaternion, t: f64) -> Quaternion {
+    // q and -q are the same rotation; pick whichever is nearer a.
+    let mut cos_theta = a.dot(b);
+    let b = if cos_theta < 0.0 {
+        cos_theta = -cos_theta;
+        -*b
+    } else {
+        *b
+    };
+
+    if cos_theta > SLERP_LINEAR_CUTOFF {
+        return (*a * (1.0 - t) + b * t).normalised()
+    }
+
+    let theta = cos_theta.acos();
+    let sin_theta = theta.sin();
+    let weight_a = ((1.0 - t) * theta).sin() / sin_theta;
+    let weight_b = (t * theta).sin() / sin_theta;
+    *a * weight_a + b * weight_b
+}
+
+
+// Operators
+
+impl Neg for Quaternion {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Quaternion::from(-self.scalar, -self.vector)
+    }
+}
+
+impl Add for Quaternion {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Quaternion::from(self.scalar + other.scalar, self.vector + other.vector)
+    }
+}
+
+// The Hamilton product: rotating by `self * other` is rotating by other, then self.
+impl Mul for Quaternion {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Quaternion::from(
+            self.scalar * other.scalar - dot_product(&self.vector, &other.vector),
+            other.vector * self.scalar + self.vector * other.scalar + cross_product(&self.vector, &other.vector)
+        )
+    }
+}
+
+impl Mul<f64> for Quaternion {
+    type Output = Self;
+
+    fn mul(self, other: f64) -> Self {
+        Quaternion::from(self.scalar * other, self.vector * other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_vectors_close(a: & Vector3, b: & Vector3) {
+        assert!((*a - *b).length() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    // q and -q are the same rotation.
+    fn assert_same_rotation(a: & Quaternion, b: & Quaternion) {
+        assert!((a.dot(b).abs() - 1.0).abs() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn rotates_like_the_matrix() {
+        let vector = Vector3::build(0.3, -1.2, 2.0);
+        for (axis, degrees) in [(Vector3::build(0.0, 0.0, 1.0), 90.0), (Vector3::build(1.0, 2.0, -0.5), 37.0), (Vector3::build(-1.0, 0.0, 1.0), 200.0)] {
+            let quaternion = Quaternion::from_axis_angle(&axis, degrees);
+            let matrix = Matrix3::rotation(&axis, degrees);
+
+            assert_vectors_close(&quaternion.rotate(&vector), &(matrix * vector));
+            assert_vectors_close(&(quaternion.to_matrix() * vector), &(matrix * vector));
+            assert_same_rotation(&Quaternion::from_matrix(&matrix), &quaternion);
+        }
+    }
+
+    #[test]
+    fn slerp_runs_from_one_end_to_the_other() {
+        let z = Vector3::build(0.0, 0.0, 1.0);
+        let (a, b) = (Quaternion::identity(), Quaternion::from_axis_angle(&z, 90.0));
+
+        assert_same_rotation(&slerp(&a, &b, 0.0), &a);
+        assert_same_rotation(&slerp(&a, &b, 1.0), &b);
+        assert_same_rotation(&slerp(&a, &b, 0.5), &Quaternion::from_axis_angle(&z, 45.0));
+    }
+
+    #[test]
+    fn slerp_takes_the_short_way_round() {
+        let z = Vector3::build(0.0, 0.0, 1.0);
+        // 270 degrees one way is 90 the other, and its quaternion has a negative dot with the identity.
+        let (a, b) = (Quaternion::identity(), Quaternion::from_axis_angle(&z, 270.0));
+        assert!(a.dot(&b) < 0.0);
+
+        assert_same_rotation(&slerp(&a, &b, 0.5), &Quaternion::from_axis_angle(&z, -45.0));
+        assert_same_rotation(&slerp(&a, &-b, 0.5), &slerp(&a, &b, 0.5));
+    }
+}
@@ -1,5 +1,6 @@
 use statrs::distribution::Normal;
 use rand::distributions::{Distribution, Uniform};
+use rand::{ Error, RngCore };
 
 pub enum Distributions {
     NORMAL(f64, f64),
@@ -7,32 +8,108 @@ pub enum Distributions {
     UNIFORM_INCL(f64, f64)
 }
 
+// Generator.
+
+// xoshiro256++: a small, fast generator whose whole state is four words, so a
+// fresh one per pixel is cheap. Seeded through splitmix64 as its authors suggest.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Prng {
+    state: [u64; 4]
+}
+
+fn splitmix64(x: &mut u64) -> u64 {
+    *x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *x;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl Prng {
+    pub fn seed(seed: u64) -> Prng {
+        let mut x = seed;
+        Prng {
+            state: [splitmix64(&mut x), splitmix64(&mut x), splitmix64(&mut x), splitmix64(&mut x)]
+        }
+    }
+
+    // Independent generator for one of many streams (e.g. pixels) under the same seed,
+    // so results don't depend on which thread happens to draw from it.
+    pub fn seed_stream(seed: u64, stream: u64) -> Prng {
+        let mut x = stream;
+        Prng::seed(seed ^ splitmix64(&mut x))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+        let t = s[1] << 17;
+
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        result
+    }
+
+    // Uniform in [0.0, 1.0), using the top 53 bits.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+}
+
+// Lets the rand/statrs distributions draw from a Prng.
+impl RngCore for Prng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        Prng::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = Prng::next_u64(self).to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
 // Sampling.
 
-pub fn sample(dist_type: Distributions) -> f64 {
-    let mut rng = rand::rngs::OsRng;
+pub fn sample(rng: &mut Prng, dist_type: Distributions) -> f64 {
     match dist_type {
         Distributions::NORMAL(mu, std_dev) => {
-            Normal::new(mu, std_dev).expect("Normal distribution failed to initialize.").sample(&mut rng)
+            Normal::new(mu, std_dev).expect("Normal distribution failed to initialize.").sample(rng)
         }
         Distributions::UNIFORM (loc, width) => {
-            Uniform::new(loc, width).sample(&mut rng)
+            Uniform::new(loc, width).sample(rng)
         }
         Distributions::UNIFORM_INCL (loc, width) => {
-            Uniform::new_inclusive(loc, width).sample(&mut rng)
+            Uniform::new_inclusive(loc, width).sample(rng)
         }
     }
 }
 
 
-pub fn random_f64(min: f64, max: f64) -> f64 {
-    // Returns a random f64 in the interval [0.0, 1.0)
-    sample(Distributions::UNIFORM(min, max))
+pub fn random_f64(rng: &mut Prng, min: f64, max: f64) -> f64 {
+    // Returns a random f64 in the interval [min, max)
+    min + (max - min) * rng.next_f64()
 }
 
-pub fn random_f64_standard() -> f64 {
+pub fn random_f64_standard(rng: &mut Prng) -> f64 {
     // Returns a random f64 in the interval [0.0, 1.0)
-    sample(Distributions::UNIFORM(0.0, 1.0))
+    rng.next_f64()
 }
 
 
@@ -52,4 +129,4 @@ pub fn standard_uniform() -> Uniform<f64> {
 
 pub fn standard_uniform_incl() -> Uniform<f64> {
     Uniform::new_inclusive(0.0, 1.0)
-}
\ No newline at end of file
+}
@@ -3,31 +3,41 @@ use super::vec3::Point3;
 
 pub struct Ray {
     origin: Point3,
-    direction: Vector3
+    direction: Vector3,
+    // When the ray was sent, for objects that move while the shutter is open.
+    time: f64
 }
 
 impl Ray {
     pub fn new() -> Ray {
         Ray {
             origin: Point3::new(),
-            direction: Vector3::new()
+            direction: Vector3::new(),
+            time: 0.0
         }
     }
 
     pub fn build(origin: & Point3, direction: & Vector3) -> Ray {
         Ray {
             origin: origin.clone(),
-            direction: direction.clone()
+            direction: direction.clone(),
+            time: 0.0
         }
     }
 
     pub fn from(origin: Point3, direction: Vector3) -> Ray {
         Ray {
             origin: origin,
-            direction: direction
+            direction: direction,
+            time: 0.0
         }
     }
 
+    pub fn with_time(mut self, time: f64) -> Ray {
+        self.time = time;
+        self
+    }
+
     pub fn origin(& self) -> & Point3{
         return &self.origin
     }
@@ -36,7 +46,17 @@ impl Ray {
         return &self.direction
     }
 
+    pub fn time(& self) -> f64 {
+        self.time
+    }
+
     pub fn at(& self, t: & f64) -> Point3 {
         return &self.origin + &(&self.direction * t);
     }
-}
\ No newline at end of file
+}
+
+impl Default for Ray {
+    fn default() -> Ray {
+        Ray::new()
+    }
+}
@@ -1,9 +1,7 @@
 use core::panic;
 use std::ops::{ Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign };
 
-use rand::{ distributions::Distribution, rngs::OsRng };
-
-use super::random::{ random_f64, random_f64_standard, standard_normal };
+use super::random::{ random_f64, random_f64_standard, Prng };
 
 #[derive(Debug)]
 #[derive(Clone, Copy)]
@@ -29,12 +27,12 @@ impl Vector3 {
         }
     }
 
-    pub fn random(min: f64, max: f64) -> Vector3 {
-        Vector3::build(random_f64(min, max), random_f64(min, max), random_f64(min, max))
+    pub fn random(rng: &mut Prng, min: f64, max: f64) -> Vector3 {
+        Vector3::build(random_f64(rng, min, max), random_f64(rng, min, max), random_f64(rng, min, max))
     }
 
-    pub fn random_standard() -> Vector3 {
-        Vector3::build(random_f64_standard(), random_f64_standard(), random_f64_standard())
+    pub fn random_standard(rng: &mut Prng) -> Vector3 {
+        Vector3::build(random_f64_standard(rng), random_f64_standard(rng), random_f64_standard(rng))
     }
 
 
@@ -66,6 +64,12 @@ impl Vector3 {
 
 }
 
+impl Default for Vector3 {
+    fn default() -> Vector3 {
+        Vector3::new()
+    }
+}
+
 
 // Index Operators
 
@@ -316,36 +320,36 @@ pub fn dot_product(vector1: & Vector3, vector2: & Vector3) -> f64 {
     vector1.x() * vector2.x() + vector1.y() * vector2.y() + vector1.z() * vector2.z()
 }
 
-pub fn random_in_unit_sphere() -> Vector3 {
+pub fn random_in_unit_sphere(rng: &mut Prng) -> Vector3 {
     loop {
-        let p = Vector3::random(-1.0, 1.0);
+        let p = Vector3::random(rng, -1.0, 1.0);
         if p.length_squared() < 1.0 { return p; }
     }
 }
 
 // This might need to be checked if things don't seem properly diffuse!
-pub fn random_unit_vector() -> Vector3 {
+pub fn random_unit_vector(rng: &mut Prng) -> Vector3 {
     // let x = standard_normal().sample(&mut OsRng);
     // let y = standard_normal().sample(&mut OsRng);
     // let z = standard_normal().sample(&mut OsRng);
     // Vector3::build(x, y, z) / f64::sqrt(x * x + y * y + z * z)
-    unit_vector(&random_in_unit_sphere())
+    unit_vector(&random_in_unit_sphere(rng))
 }
 
 // This might need to be checked if things don't seem properly diffuse!
-pub fn random_in_unit_disk() -> Vector3 {
+pub fn random_in_unit_disk(rng: &mut Prng) -> Vector3 {
     // let x = standard_normal().sample(&mut OsRng);
     // let y = standard_normal().sample(&mut OsRng);
     // let z = 0.0;
     // Vector3::build(x, y, z) / f64::sqrt(x * x + y * y)
     loop {
-        let p = Vector3::build(random_f64(-1.0, 1.0), random_f64(-1.0, 1.0), 0.0);
+        let p = Vector3::build(random_f64(rng, -1.0, 1.0), random_f64(rng, -1.0, 1.0), 0.0);
         if p.length_squared() < 1.0 { return p  ; }
     }
 }
 
-pub fn random_on_hemisphere(normal: & Vector3) -> Vector3 {
-    let candidate_vector = random_unit_vector();
+pub fn random_on_hemisphere(rng: &mut Prng, normal: & Vector3) -> Vector3 {
+    let candidate_vector = random_unit_vector(rng);
     if dot_product(&candidate_vector, normal) > 0.0 {
         candidate_vector
     } else {
@@ -353,6 +357,17 @@ pub fn random_on_hemisphere(normal: & Vector3) -> Vector3 {
     }
 }
 
+// A direction in the hemisphere around +z, denser towards the pole with the
+// cosine of its angle from it: pdf = z / pi.
+pub fn random_cosine_direction(rng: &mut Prng) -> Vector3 {
+    let r1 = random_f64_standard(rng);
+    let r2 = random_f64_standard(rng);
+
+    let phi = 2.0 * std::f64::consts::PI * r1;
+    let radius = f64::sqrt(r2);
+    Vector3::build(phi.cos() * radius, phi.sin() * radius, f64::sqrt(1.0 - r2))
+}
+
 pub fn reflect(vector: & Vector3, normal: & Vector3) -> Vector3 {
     vector - &(normal * &(dot_product(vector, normal) * 2.0))
 }
@@ -362,4 +377,4 @@ pub fn refract(vector: & Vector3, normal: & Vector3, refractive_index_ratio: f64
     let r_out_perp = (vector + &(normal * cos_theta)) * refractive_index_ratio;
     let r_out_parallel = (-f64::sqrt(1.0 - r_out_perp.length_squared())) * normal;
     r_out_perp + r_out_parallel
-}
\ No newline at end of file
+}
@@ -1 +1,7 @@
+pub mod adaptive;
+pub mod aov;
 pub mod camera;
+pub mod denoiser;
+pub mod framebuffer;
+pub mod integrator;
+pub mod output;
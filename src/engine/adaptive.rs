@@ -0,0 +1,179 @@
+use crate::common::colour::Colour;
+
+use super::framebuffer::Framebuffer;
+
+// Dark pixels are judged as if at least this bright, so a handful of black
+// samples doesn't count as settled on its own.
+const MINIMUM_BRIGHTNESS: f64 = 1e-4;
+
+// Takes samples for a pixel only until its colour has settled, so flat sky
+// stops early and the samples go to caustics, soft shadows and the like. The
+// camera's samples per pixel is the most any pixel gets.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub struct AdaptiveSampling {
+    // Every pixel gets at least these, so a few lucky samples can't look settled.
+    min_samples: i32,
+    // The standard error of a pixel's brightness as displayed, 0 to 1, below
+    // which it is left alone.
+    threshold: f64
+}
+
+impl AdaptiveSampling {
+    pub fn new() -> AdaptiveSampling {
+        AdaptiveSampling {
+            min_samples: 16,
+            threshold: 0.01
+        }
+    }
+
+    pub fn build(min_samples: i32, threshold: f64) -> AdaptiveSampling {
+        // Below zero no pixel would ever count as settled.
+        assert!(threshold > 0.0, "AdaptiveSampling: threshold must be greater than zero, not {}.", threshold);
+
+        AdaptiveSampling {
+            min_samples: min_samples.max(2),
+            threshold
+        }
+    }
+
+    pub fn min_samples(& self) -> i32 {
+        self.min_samples
+    }
+
+    // The display takes roughly the square root of the linear brightness, so
+    // an error of e at brightness b shows up as about e / (2 sqrt(b)).
+    pub fn converged(& self, estimate: & PixelEstimate) -> bool {
+        if estimate.count() < self.min_samples {
+            return false
+        }
+        let standard_error = (estimate.variance() / estimate.count() as f64).sqrt();
+        standard_error / (2.0 * estimate.mean().max(MINIMUM_BRIGHTNESS).sqrt()) <= self.threshold
+    }
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> AdaptiveSampling {
+        AdaptiveSampling::new()
+    }
+}
+
+// The running mean and variance of a pixel's samples, updated one sample at
+// a time with Welford's method, which doesn't lose precision to cancellation.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub struct PixelEstimate {
+    count: i32,
+    mean: f64,
+    // Sum of squared differences from the mean.
+    squared_deviations: f64
+}
+
+impl PixelEstimate {
+    pub fn new() -> PixelEstimate {
+        PixelEstimate {
+            count: 0,
+            mean: 0.0,
+            squared_deviations: 0.0
+        }
+    }
+
+    pub fn add(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.squared_deviations += delta * (value - self.mean);
+    }
+
+    pub fn count(& self) -> i32 {
+        self.count
+    }
+
+    pub fn mean(& self) -> f64 {
+        self.mean
+    }
+
+    // The sample variance; zero until there are two samples.
+    pub fn variance(& self) -> f64 {
+        if self.count < 2 {
+            return 0.0
+        }
+        self.squared_deviations / (self.count - 1) as f64
+    }
+}
+
+impl Default for PixelEstimate {
+    fn default() -> PixelEstimate {
+        PixelEstimate::new()
+    }
+}
+
+// Colours per-pixel sample counts from dark blue for the fewest, through
+// green, to red for the most, for seeing where adaptive sampling spent its time.
+pub fn heatmap(sample_counts: & Framebuffer) -> Framebuffer {
+    let counts = sample_counts.pixels();
+    let fewest = counts.iter().map(|count| count.x()).fold(f64::INFINITY, f64::min);
+    let most = counts.iter().map(|count| count.x()).fold(f64::NEG_INFINITY, f64::max);
+    let range = (most - fewest).max(1.0);
+
+    let stops = [
+        Colour::build(0.0, 0.0, 0.3),
+        Colour::build(0.0, 0.4, 1.0),
+        Colour::build(0.0, 0.8, 0.2),
+        Colour::build(1.0, 0.9, 0.0),
+        Colour::build(1.0, 0.0, 0.0)
+    ];
+
+    let mut result = Framebuffer::new(sample_counts.width(), sample_counts.height());
+    for y in 0..sample_counts.height() {
+        let start = y * sample_counts.width();
+        for (x, pixel) in result.row_mut(y).iter_mut().enumerate() {
+            let position = (counts[start + x].x() - fewest) / range * (stops.len() - 1) as f64;
+            let index = (position.floor() as usize).min(stops.len() - 2);
+            let t = position - index as f64;
+            *pixel = stops[index] * (1.0 - t) + stops[index + 1] * t;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::random::{ random_f64, Prng };
+
+    use super::*;
+
+    #[test]
+    fn running_estimate_matches_two_passes() {
+        let mut rng = Prng::seed(9);
+        // A large offset is where the one-pass sum of squares would lose precision.
+        let values: Vec<f64> = (0..1000).map(|_| 1e6 + random_f64(&mut rng, 0.0, 2.0)).collect();
+
+        let mut estimate = PixelEstimate::new();
+        for value in &values {
+            estimate.add(*value);
+        }
+
+        let count = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / count;
+        let variance = values.iter().map(|value| (value - mean) * (value - mean)).sum::<f64>() / (count - 1.0);
+
+        assert_eq!(estimate.count(), values.len() as i32);
+        assert!((estimate.mean() - mean).abs() < 1e-9 * mean, "mean {} != {}", estimate.mean(), mean);
+        assert!((estimate.variance() - variance).abs() < 1e-6 * variance, "variance {} != {}", estimate.variance(), variance);
+    }
+
+    #[test]
+    fn variance_is_zero_until_there_are_two_samples() {
+        let mut estimate = PixelEstimate::new();
+        assert_eq!(estimate.variance(), 0.0);
+        estimate.add(3.0);
+        assert_eq!((estimate.mean(), estimate.variance()), (3.0, 0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "threshold must be greater than zero")]
+    fn rejects_a_negative_threshold() {
+        AdaptiveSampling::build(16, -0.01);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::common::colour::Colour;
+use crate::common::ray::Ray;
+use crate::common::vec3::{ Point3, Vector3 };
+
+use crate::primitive::hittable::HitRecord;
+
+use super::framebuffer::Framebuffer;
+
+// What one camera ray found at its first hit.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub struct AovSample {
+    // World space and facing the ray, as the materials see it.
+    pub normal: Vector3,
+    pub albedo: Colour,
+    // Distance from the camera along the ray.
+    pub depth: f64,
+    pub position: Point3,
+    pub material_id: usize,
+    pub object_id: usize
+}
+
+impl AovSample {
+    // A ray that hit nothing: zeros, apart from the albedo, which is what the
+    // ray sees instead.
+    pub fn new(background: Colour) -> AovSample {
+        AovSample {
+            normal: Vector3::new(),
+            albedo: background,
+            depth: 0.0,
+            position: Point3::new(),
+            material_id: 0,
+            object_id: 0
+        }
+    }
+
+    pub fn from(ray: & Ray, hit_rec: & HitRecord) -> AovSample {
+        AovSample {
+            normal: hit_rec.normal(),
+            albedo: hit_rec.material.albedo(hit_rec),
+            depth: hit_rec.t * ray.direction().length(),
+            position: hit_rec.point(),
+            material_id: hit_rec.material_id,
+            object_id: hit_rec.object_id
+        }
+    }
+
+    // The mean of a pixel's samples. IDs can't be averaged, so it takes those
+    // of the first sample.
+    pub fn average(samples: & [AovSample]) -> AovSample {
+        let first = samples.first().expect("AovSample: can't average no samples.");
+        let scale = 1.0 / samples.len() as f64;
+        let mut average = *first;
+
+        for sample in &samples[1..] {
+            average.normal += sample.normal;
+            average.albedo += sample.albedo;
+            average.depth += sample.depth;
+            average.position += sample.position;
+        }
+        average.normal = average.normal * scale;
+        average.albedo = average.albedo * scale;
+        average.depth *= scale;
+        average.position = average.position * scale;
+
+        average
+    }
+}
+
+// Arbitrary output variables: what the camera rays hit first, kept in buffers
+// beside the beauty image as guides for denoising and as mattes, one averaged
+// AovSample per pixel. Scalars are stored grey, in all three channels.
+pub struct Aovs {
+    pub normal: Framebuffer,
+    pub albedo: Framebuffer,
+    pub depth: Framebuffer,
+    pub position: Framebuffer,
+    pub material_id: Framebuffer,
+    pub object_id: Framebuffer
+}
+
+impl Aovs {
+    pub fn new(width: usize, height: usize) -> Aovs {
+        Aovs {
+            normal: Framebuffer::new(width, height),
+            albedo: Framebuffer::new(width, height),
+            depth: Framebuffer::new(width, height),
+            position: Framebuffer::new(width, height),
+            material_id: Framebuffer::new(width, height),
+            object_id: Framebuffer::new(width, height)
+        }
+    }
+
+    pub fn set_row(&mut self, y: usize, row: & [AovSample]) {
+        let grey = |value: f64| Colour::build(value, value, value);
+
+        for (x, sample) in row.iter().enumerate() {
+            self.normal.row_mut(y)[x] = sample.normal;
+            self.albedo.row_mut(y)[x] = sample.albedo;
+            self.depth.row_mut(y)[x] = grey(sample.depth);
+            self.position.row_mut(y)[x] = sample.position;
+            self.material_id.row_mut(y)[x] = grey(sample.material_id as f64);
+            self.object_id.row_mut(y)[x] = grey(sample.object_id as f64);
+        }
+    }
+
+    // Each buffer with the name it's written out under.
+    pub fn buffers(& self) -> [(&'static str, & Framebuffer); 6] {
+        [
+            ("normal", &self.normal),
+            ("albedo", &self.albedo),
+            ("depth", &self.depth),
+            ("position", &self.position),
+            ("material_id", &self.material_id),
+            ("object_id", &self.object_id)
+        ]
+    }
+}
@@ -1,61 +1,78 @@
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::sync::Mutex;
+use std::thread;
+
 use crate::common::mat3::Matrix3;
-use crate::common::random::{ sample, Distributions };
-use crate::common::vec3::{ cross_product, random_in_unit_disk, unit_vector, Point3, Vector3 };
-use crate::common::colour::{ Colour, write_colour };
+use crate::common::random::{ random_f64, Prng };
+use crate::common::vec3::{ random_in_unit_disk, unit_vector, Point3, Vector3 };
+use crate::common::colour::{ luminance, Colour };
 use crate::common::interval::Interval;
 use crate::common::ray::Ray;
 use crate::common::{ degrees_to_radians, RAY_MINIMUM_DISTANCE_BEFORE_HIT };
 
 use crate::primitive::hittable::Hittable;
+use crate::primitive::hittable_list::HittableList;
 
-const PPM_FORMAT : &str = "P3\n";
-const PPM_MAX_COLOUR : i32 = 255;
-
-
-// Raycast helpers.
-
-fn ray_colour(ray: & Ray, depth_remaining: i32, world: & impl Hittable) -> Colour {
-    if depth_remaining <= 0 {
-        return Colour::new()
-    }
-
-    let (hit_anything, hit_rec) = 
-        world.hit(ray, &Interval::build(RAY_MINIMUM_DISTANCE_BEFORE_HIT, f64::INFINITY));
+use super::adaptive::{ AdaptiveSampling, PixelEstimate };
+use super::aov::{ AovSample, Aovs };
+use super::denoiser::Denoiser;
+use super::framebuffer::Framebuffer;
+use super::integrator::{ Integrator, PathTracer };
 
-    if hit_anything {
-        let hit_rec = hit_rec.expect("camera::ray_colour: hit registered, but no hit record.");
+pub const DEFAULT_ROULETTE_DEPTH: i32 = 5;
 
-        let (scattered, attenuation, scattered_ray) = 
-            hit_rec.material.scatter(ray, &hit_rec);
 
-        if scattered {
-            return attenuation * ray_colour(&scattered_ray, depth_remaining - 1, world);
-        }
-
-        return Colour::build(0.0, 0.0, 0.0);
-    }
-
-    let unit_direction = unit_vector(ray.direction());
-    let a = (&unit_direction.y() + 1.0) * 0.5;
-    Colour::build(1.0, 1.0, 1.0) * (1.0 - a) + Colour::build(0.5, 0.7, 1.0) * (a)
-}
+// Raycast helpers.
 
 // I think this is a function mostly to demonstrate that the pixel shape from
 // which samples are drawn can be switched to produce different types of images.
 // Should probably be partitioned into random, then, or something else.
-fn sample_square() -> Vector3 {
+fn sample_square(rng: &mut Prng) -> Vector3 {
     // Produces a random vector within the [-0.5, -0.5, 0.0] - [0.5, 0.5, 0.0] unit square.
     Vector3::build(
-        sample(Distributions::UNIFORM_INCL(-0.5, 0.5)), 
-        sample(Distributions::UNIFORM_INCL(-0.5, 0.5)), 
+        random_f64(rng, -0.5, 0.5),
+        random_f64(rng, -0.5, 0.5),
         0.0
     )
 }
 
-// Output helper.
+// What a ray sees when it escapes the scene.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub enum Background {
+    // The white-to-blue gradient from the tutorial, lighting the scene from everywhere.
+    Sky,
+    // A flat colour; black leaves the scene lit only by its emissive materials.
+    Solid(Colour)
+}
+
+impl Background {
+    pub fn colour(&self, ray: & Ray) -> Colour {
+        match self {
+            Background::Sky => {
+                let unit_direction = unit_vector(ray.direction());
+                let a = (&unit_direction.y() + 1.0) * 0.5;
+                Colour::build(1.0, 1.0, 1.0) * (1.0 - a) + Colour::build(0.5, 0.7, 1.0) * (a)
+            }
+            Background::Solid(colour) => *colour
+        }
+    }
+}
+
+// Everything a render produces.
+pub struct RenderOutput {
+    pub image: Framebuffer,
+    // Only recorded when asked for, or for the denoiser.
+    pub aovs: Option<Aovs>,
+    // How many samples each pixel took, stored grey. They're all the camera's
+    // samples per pixel unless sampling is adaptive.
+    pub sample_counts: Framebuffer
+}
+
+// Threading helper.
 
-fn print_ppm_header(ppm_format : &str, image_height : i32, image_width : i32, ppm_max_colour : i32) {
-    println!("{0}{1} {2}\n{3}", ppm_format, image_width, image_height, ppm_max_colour);
+fn default_thread_count() -> usize {
+    thread::available_parallelism().map(|count| count.get()).unwrap_or(1)
 }
 
 pub struct Camera {
@@ -64,6 +81,8 @@ pub struct Camera {
     image_width: i32,
     samples_per_pixel: i32,
     max_depth: i32,
+    // Bounces before paths may be ended early by Russian roulette.
+    roulette_depth: i32,
 
     vertical_fov_degrees: f64,
     lookfrom: Point3,
@@ -73,10 +92,29 @@ pub struct Camera {
     defocus_angle: f64,
     focus_dist: f64,
 
+    // Each ray is sent at a random time while the shutter is open, which blurs
+    // whatever moves. Objects move over times 0 to 1.
+    shutter_open: f64,
+    shutter_close: f64,
+
+    background: Background,
+
+    // When None, paths are traced with max_depth and roulette_depth.
+    integrator: Option<Box<dyn Integrator>>,
+    // Filters the image once it's rendered, guided by the AOVs.
+    denoiser: Option<Denoiser>,
+    // Stops sampling pixels once they settle, when set.
+    adaptive: Option<AdaptiveSampling>,
+
+    thread_count: usize,
+    seed: u64,
+
+    // Overrides the height given by the aspect ratio, when set.
+    fixed_image_height: Option<i32>,
+
     // computed attributes
     image_height: Option<i32>,
     viewport_height: Option<f64>,
-    pixel_samples_scale: Option<f64>,
     center: Option<Point3>,
     pixel00_loc: Option<Point3>,
     pixel_delta_width: Option<Vector3>,
@@ -95,6 +133,7 @@ impl Camera {
             image_width: 100,
             samples_per_pixel: 10,
             max_depth: 10,
+            roulette_depth: DEFAULT_ROULETTE_DEPTH,
             vertical_fov_degrees: 90.0,
             lookfrom: Point3::build(0.0, 0.0, 0.0),
             lookat: Point3::build(0.0, 0.0, -1.0),
@@ -102,9 +141,22 @@ impl Camera {
             defocus_angle: 0.0,
             focus_dist: 10.0,
 
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+
+            background: Background::Sky,
+
+            integrator: None,
+            denoiser: None,
+            adaptive: None,
+
+            thread_count: default_thread_count(),
+            seed: 0,
+
+            fixed_image_height: None,
+
             image_height: None,
             viewport_height: None,
-            pixel_samples_scale: None,
             center: None,
             pixel00_loc: None,
             pixel_delta_width: None,
@@ -134,6 +186,7 @@ impl Camera {
             image_width,
             samples_per_pixel,
             max_depth,
+            roulette_depth: DEFAULT_ROULETTE_DEPTH,
             vertical_fov_degrees,
             lookfrom,
             lookat,
@@ -141,9 +194,22 @@ impl Camera {
             defocus_angle,
             focus_dist,
 
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+
+            background: Background::Sky,
+
+            integrator: None,
+            denoiser: None,
+            adaptive: None,
+
+            thread_count: default_thread_count(),
+            seed: 0,
+
+            fixed_image_height: None,
+
             image_height: None,
             viewport_height: None,
-            pixel_samples_scale: None,
             center: None,
             pixel00_loc: None,
             pixel_delta_width: None,
@@ -156,16 +222,82 @@ impl Camera {
         }
     }
 
+    // A dimension left out follows from the other one and the aspect ratio.
+    // Giving both overrides the aspect ratio.
+    pub fn set_image_size(&mut self, width: Option<i32>, height: Option<i32>) {
+        match (width, height) {
+            (Some(width), Some(height)) => {
+                self.image_width = width.max(1);
+                self.fixed_image_height = Some(height.max(1));
+            }
+            (Some(width), None) => {
+                self.image_width = width.max(1);
+                self.fixed_image_height = None;
+            }
+            (None, Some(height)) => {
+                self.image_width = ((height as f64 * self.aspect_ratio).round() as i32).max(1);
+                self.fixed_image_height = Some(height.max(1));
+            }
+            (None, None) => {}
+        }
+    }
+
+    pub fn set_samples_per_pixel(&mut self, samples_per_pixel: i32) {
+        self.samples_per_pixel = samples_per_pixel.max(1);
+    }
+
+    pub fn max_depth(&self) -> i32 {
+        self.max_depth
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: i32) {
+        self.max_depth = max_depth.max(1);
+    }
+
+    // A depth of max_depth or more turns Russian roulette off.
+    pub fn set_roulette_depth(&mut self, roulette_depth: i32) {
+        self.roulette_depth = roulette_depth.max(0);
+    }
+
+    // Opening and closing at the same time freezes everything at that moment.
+    pub fn set_shutter(&mut self, open: f64, close: f64) {
+        self.shutter_open = open.min(close);
+        self.shutter_close = open.max(close);
+    }
+
+    pub fn set_background(&mut self, background: Background) {
+        self.background = background;
+    }
+
+    // None goes back to the path tracer.
+    pub fn set_integrator(&mut self, integrator: Option<Box<dyn Integrator>>) {
+        self.integrator = integrator;
+    }
+
+    pub fn set_denoiser(&mut self, denoiser: Option<Denoiser>) {
+        self.denoiser = denoiser;
+    }
+
+    // samples_per_pixel becomes the most any pixel gets.
+    pub fn set_adaptive(&mut self, adaptive: Option<AdaptiveSampling>) {
+        self.adaptive = adaptive;
+    }
+
+    pub fn set_thread_count(&mut self, thread_count: usize) {
+        self.thread_count = thread_count.max(1);
+    }
+
+    // Renders with the same seed are bit-identical, whatever the thread count.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
     // This is to facilitate initialize()...
     // but initialize() code should really be part of construction, or at least in Rust.
     fn image_height(&self) -> i32 {
         self.image_height.clone().expect("Camera: image_height needed, but not initialized.")
     }
 
-    fn pixel_samples_scale(&self) -> f64 {
-        self.pixel_samples_scale.clone().expect("Camera: pixel_samples_scale needed, but not initialized.")
-    }
-
     fn center(&self) -> Point3 {
         self.center.clone().expect("Camera: center needed, but not initialized.")
     }
@@ -194,26 +326,32 @@ impl Camera {
         self.defocus_disk_v.clone().expect("Camera: defocus_disk_v needed, but not initialized")
     }
 
-    fn defocus_disk_sample(&self) -> Point3 {
-        let p = random_in_unit_disk();
+    fn defocus_disk_sample(&self, rng: &mut Prng) -> Point3 {
+        let p = random_in_unit_disk(rng);
         self.center() + (self.defocus_disk_u() * p[0]) + (self.defocus_disk_v() * p[1])
     }
     
-    fn ray_to_pixel(&self, x: i32, y: i32) -> Ray {
-        let offset = sample_square();
+    fn ray_to_pixel(&self, x: i32, y: i32, rng: &mut Prng) -> Ray {
+        let offset = sample_square(rng);
         let pixel_sample = 
             self.pixel00_loc() + 
             (self.pixel_delta_width() * (x as f64 + offset.x()))+ 
             (self.pixel_delta_height() * (y as f64 + offset.y()));
 
-        let ray_origin = if self.defocus_angle <= 0.0 { self.center() } else { self.defocus_disk_sample() };
+        let ray_origin = if self.defocus_angle <= 0.0 { self.center() } else { self.defocus_disk_sample(rng) };
         let ray_direction = &pixel_sample - &ray_origin;
 
-        Ray::from(ray_origin, ray_direction)
+        let ray_time = if self.shutter_close > self.shutter_open {
+            random_f64(rng, self.shutter_open, self.shutter_close)
+        } else {
+            self.shutter_open
+        };
+
+        Ray::from(ray_origin, ray_direction).with_time(ray_time)
     }
 
     pub fn initialize(&mut self) {
-        let mut projected_height = (self.image_width as f64 / self.aspect_ratio) as i32;
+        let mut projected_height = self.fixed_image_height.unwrap_or((self.image_width as f64 / self.aspect_ratio) as i32);
         if projected_height < 1 { 
             self.image_height = Some(1);
             projected_height = 1;
@@ -221,8 +359,6 @@ impl Camera {
             self.image_height = Some(projected_height);
         };
 
-        self.pixel_samples_scale = Some(1.0 / self.samples_per_pixel as f64);
-
         self.center = Some(self.lookfrom);
 
         // Viewport.
@@ -232,10 +368,9 @@ impl Camera {
         let viewport_width = self.viewport_height.expect("Camera: viewport height was just set but no longer exists.") * (self.image_width as f64 / projected_height as f64);
 
         // Calculate camera coordinate frame basis vectors.
-        let w = unit_vector(&(self.lookfrom - self.lookat));
-        let u = unit_vector(&cross_product(&self.vup, &w));
-        let v = cross_product(&w, &u);
-        self.frame_basis = Some(Matrix3::build(&u, &v, &w));
+        self.frame_basis = Some(Matrix3::look_at(&self.lookfrom, &self.lookat, &self.vup));
+        let frame = self.frame_basis();
+        let (u, v, w) = (*frame.u(), *frame.v(), *frame.w());
 
         // Viewport edge vectors.
         let viewport_width_vector = u * viewport_width;
@@ -261,27 +396,249 @@ impl Camera {
     }
 
 
-    pub fn render(&self, world: & impl Hittable) {
+    // The colour of each pixel in row y, how many samples it took, and with
+    // `record_aovs` what its samples hit first.
+    fn render_row(
+        &self,
+        y: i32,
+        integrator: & dyn Integrator,
+        world: & dyn Hittable,
+        lights: & HittableList,
+        record_aovs: bool
+    ) -> (Vec<Colour>, Vec<Colour>, Option<Vec<AovSample>>) {
+        let mut sample_counts = Vec::with_capacity(self.image_width as usize);
+        let mut aov_row = Vec::new();
+        let mut aov_samples = Vec::new();
+
+        let colours = (0..self.image_width).map(|x| {
+            // Each pixel draws from its own stream, so the image doesn't depend on scheduling.
+            let pixel_index = y as u64 * self.image_width as u64 + x as u64;
+            let mut rng = Prng::seed_stream(self.seed, pixel_index);
+
+            let mut pixel_colour = Colour::new();
+            let mut estimate = PixelEstimate::new();
+            aov_samples.clear();
+            for _ in 0..self.samples_per_pixel {
+                let r = self.ray_to_pixel(x, y, &mut rng);
+                if record_aovs {
+                    // A copy of the generator makes the same choices the integrator
+                    // is about to, e.g. in volumes, without changing the image.
+                    let mut aov_rng = rng.clone();
+                    aov_samples.push(match world.hit(&r, &Interval::build(RAY_MINIMUM_DISTANCE_BEFORE_HIT, f64::INFINITY), &mut aov_rng) {
+                        (true, Some(hit_rec)) => AovSample::from(&r, &hit_rec),
+                        _ => AovSample::new(self.background.colour(&r))
+                    });
+                }
+
+                let sample_colour = integrator.ray_colour(r, world, lights, &self.background, &mut rng);
+                pixel_colour += sample_colour;
+                estimate.add(luminance(&sample_colour));
+                if self.adaptive.is_some_and(|adaptive| adaptive.converged(&estimate)) {
+                    break
+                }
+            }
+
+            if record_aovs {
+                aov_row.push(AovSample::average(&aov_samples));
+            }
+            let count = estimate.count() as f64;
+            sample_counts.push(Colour::build(count, count, count));
+            pixel_colour * (1.0 / count)
+        }).collect();
+
+        (colours, sample_counts, if record_aovs { Some(aov_row) } else { None })
+    }
+
+    // `lights` are sampled directly at every bounce off a diffuse surface; they
+    // should be in the world too. Other lights are only found by chance.
+    pub fn render(&self, world: & impl Hittable, lights: & HittableList) -> Framebuffer {
+        self.render_passes(world, lights, false).image
+    }
+
+    // Renders as render does, and also returns the AOVs.
+    pub fn render_with_aovs(&self, world: & impl Hittable, lights: & HittableList) -> (Framebuffer, Aovs) {
+        let output = self.render_passes(world, lights, true);
+        (output.image, output.aovs.expect("Camera: AOVs requested, but not recorded."))
+    }
+
+    // Renders the image, denoised if set to be, along with the sample counts and,
+    // with `record_aovs`, the AOVs.
+    pub fn render_passes(&self, world: & impl Hittable, lights: & HittableList, record_aovs: bool) -> RenderOutput {
         if !self.initialized {
             eprintln!("Camera: render attempted without initialization.");
             panic!();
         }
 
+        let path_tracer;
+        let integrator: & dyn Integrator = match &self.integrator {
+            Some(integrator) => integrator.as_ref(),
+            None => {
+                path_tracer = PathTracer::build(self.max_depth, self.roulette_depth);
+                &path_tracer
+            }
+        };
+        // The denoiser needs the AOVs as guides, even if nobody else does.
+        let record_aovs = record_aovs || self.denoiser.is_some();
+
         // Rendering.
-        print_ppm_header(PPM_FORMAT, self.image_height(), self.image_width, PPM_MAX_COLOUR);
-        
-        let spp = self.pixel_samples_scale();
-        for y in 0..self.image_height() {
-            eprintln!("Scanlines remaining: {}", self.image_height() - y);
-
-            for x in 0..self.image_width {
-                let mut pixel_colour = Colour::new();
-                for _ in 0..self.samples_per_pixel {
-                    let r = self.ray_to_pixel(x, y);
-                    pixel_colour += ray_colour(&r, self.max_depth, world);
-                }
-                write_colour(&(pixel_colour * spp));
+        // Threads pull whole scanlines off a shared counter until none are left,
+        // so a slow row never holds up the others.
+        let image_width = self.image_width as usize;
+        let image_height = self.image_height() as usize;
+        let next_row = AtomicUsize::new(0);
+        let rows_done = AtomicUsize::new(0);
+        let output = Mutex::new(RenderOutput {
+            image: Framebuffer::new(image_width, image_height),
+            aovs: if record_aovs { Some(Aovs::new(image_width, image_height)) } else { None },
+            sample_counts: Framebuffer::new(image_width, image_height)
+        });
+
+        thread::scope(|scope| {
+            for _ in 0..self.thread_count {
+                scope.spawn(|| loop {
+                    let y = next_row.fetch_add(1, Ordering::Relaxed);
+                    if y >= image_height {
+                        break;
+                    }
+
+                    let (row, sample_counts, aov_row) = self.render_row(y as i32, integrator, world, lights, record_aovs);
+                    let mut output = output.lock().expect("Camera: framebuffer poisoned by a failed render thread.");
+                    output.image.row_mut(y).copy_from_slice(&row);
+                    output.sample_counts.row_mut(y).copy_from_slice(&sample_counts);
+                    if let (Some(aovs), Some(aov_row)) = (&mut output.aovs, aov_row) {
+                        aovs.set_row(y, &aov_row);
+                    }
+                    drop(output);
+
+                    let done = rows_done.fetch_add(1, Ordering::Relaxed) + 1;
+                    eprintln!("Scanlines remaining: {}", image_height - done);
+                });
             }
+        });
+
+        let mut output = output.into_inner().expect("Camera: framebuffer poisoned by a failed render thread.");
+        if let (Some(denoiser), Some(guides)) = (&self.denoiser, &output.aovs) {
+            output.image = denoiser.denoise(&output.image, guides);
         }
+        output
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Camera {
+        Camera::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::materials::MatArc;
+    use crate::materials::dielectric::Dialectric;
+    use crate::materials::diffuse_light::DiffuseLight;
+    use crate::materials::isotropic::Isotropic;
+    use crate::materials::lambertian::Lambertian;
+    use crate::materials::metal::Metal;
+    use crate::primitive::constant_medium::ConstantMedium;
+    use crate::primitive::sphere::Sphere;
+
+    use super::*;
+
+    // Diffuse, metal, glass, fog and a sampled light, so every kind of
+    // bounce draws on the pixel's generator, with adaptive sampling and AOVs.
+    fn render(thread_count: usize, seed: u64) -> RenderOutput {
+        let ground: MatArc = Arc::new(Box::new(Lambertian::build_explicit(0.5, 0.5, 0.5)));
+        let metal: MatArc = Arc::new(Box::new(Metal::build_explicit(0.8, 0.6, 0.2, 0.3)));
+        let glass: MatArc = Arc::new(Box::new(Dialectric::build_explicit(1.0, 1.0, 1.0, 1.5)));
+        let fog: MatArc = Arc::new(Box::new(Isotropic::from(Colour::build(0.9, 0.9, 0.9))));
+        let light: MatArc = Arc::new(Box::new(DiffuseLight::build_explicit(6.0, 6.0, 6.0)));
+
+        let mut world = HittableList::new();
+        world.add(Sphere::build_explicit((0.0, -100.5, -1.0), 100.0, &ground));
+        world.add(Sphere::build_explicit((-1.0, 0.0, -1.5), 0.5, &metal));
+        world.add(Sphere::build_explicit((1.0, 0.0, -1.5), 0.5, &glass));
+        world.add(ConstantMedium::build(Sphere::build_explicit((0.0, 0.0, -2.0), 0.6, &fog), 1.0, &fog));
+        world.add(Sphere::build_explicit((0.0, 2.0, -1.0), 0.5, &light));
+        let lights = HittableList::build(Sphere::build_explicit((0.0, 2.0, -1.0), 0.5, &light));
+
+        let mut camera = Camera::new();
+        camera.set_image_size(Some(8), Some(6));
+        camera.set_samples_per_pixel(24);
+        camera.set_adaptive(Some(AdaptiveSampling::build(4, 0.05)));
+        camera.set_background(Background::Solid(Colour::build(0.1, 0.1, 0.2)));
+        camera.set_thread_count(thread_count);
+        camera.set_seed(seed);
+        camera.initialize();
+        camera.render_passes(&world, &lights, true)
+    }
+
+    fn bits(framebuffer: & Framebuffer) -> Vec<u64> {
+        framebuffer.pixels().iter().flat_map(|pixel| (0..3).map(|channel| pixel[channel].to_bits())).collect()
     }
-}
\ No newline at end of file
+
+    fn all_bits(output: & RenderOutput) -> Vec<u64> {
+        let aovs = output.aovs.as_ref().expect("AOVs were asked for");
+        let mut all = bits(&output.image);
+        all.extend(bits(&output.sample_counts));
+        for (_, buffer) in aovs.buffers() {
+            all.extend(bits(buffer));
+        }
+        all
+    }
+
+    // Rows go to whichever thread is free, but every pixel draws from its own stream.
+    #[test]
+    fn thread_count_does_not_change_the_image() {
+        let single = all_bits(&render(1, 7));
+        for thread_count in [2, 4] {
+            assert!(single == all_bits(&render(thread_count, 7)), "{} threads gave a different image", thread_count);
+        }
+    }
+
+    #[test]
+    fn the_seed_alone_decides_the_image() {
+        let first = all_bits(&render(3, 7));
+        assert!(first == all_bits(&render(3, 7)), "the same seed gave a different image");
+        assert!(bits(&render(3, 7).image) != bits(&render(3, 8).image), "a new seed gave the same image");
+    }
+
+    // One sample per pixel, so each pixel's AOVs are exactly what its one ray hit.
+    #[test]
+    fn aovs_record_the_first_hit() {
+        let albedo = Colour::build(0.2, 0.4, 0.6);
+        let background = Colour::build(0.1, 0.1, 0.2);
+        let ball: MatArc = Arc::new(Box::new(Lambertian::from(albedo)));
+        let centre = Point3::build(0.0, 0.0, -3.0);
+        let world = HittableList::build(Sphere::build(&centre, &1.0, &ball));
+
+        let mut camera = Camera::new();
+        camera.set_image_size(Some(9), Some(9));
+        camera.set_samples_per_pixel(1);
+        camera.set_background(Background::Solid(background));
+        camera.initialize();
+        let (_, aovs) = camera.render_with_aovs(&world, &HittableList::new());
+
+        let (mut hits, mut misses) = (0, 0);
+        for i in 0..aovs.depth.pixels().len() {
+            let normal = aovs.normal.pixels()[i];
+            let depth = aovs.depth.pixels()[i].x();
+            let position = aovs.position.pixels()[i];
+
+            if depth == 0.0 {
+                misses += 1;
+                assert!(normal.length() == 0.0 && position.length() == 0.0);
+                assert!((aovs.albedo.pixels()[i] - background).length() == 0.0);
+                continue;
+            }
+            hits += 1;
+            assert!((normal.length() - 1.0).abs() < 1e-9, "normal {:?} isn't unit length", normal);
+            assert!(((position - centre).length() - 1.0).abs() < 1e-9, "{:?} isn't on the sphere", position);
+            assert!((normal - (position - centre)).length() < 1e-9, "normal {:?} doesn't face out of the sphere", normal);
+            // The camera sits at the origin.
+            assert!((depth - position.length()).abs() < 1e-9, "depth {} isn't the distance to {:?}", depth, position);
+            assert!((aovs.albedo.pixels()[i] - albedo).length() == 0.0);
+        }
+        assert!(hits > 0 && misses > 0, "{} hits and {} misses", hits, misses);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::common::colour::{ luminance, Colour };
+use crate::common::vec3::dot_product;
+
+use super::aov::Aovs;
+use super::framebuffer::Framebuffer;
+
+// Added to the albedo before dividing by it, so black surfaces don't divide by
+// zero and dark ones aren't blown up into noise.
+const ALBEDO_EPSILON: f64 = 0.01;
+
+// A joint bilateral filter: each pixel becomes a weighted average of its
+// neighbours, where a neighbour counts for less the further off it is and the
+// more its albedo, normal and depth differ, so noise is smoothed out within a
+// surface without blurring across the edges between surfaces.
+//
+// Texture is taken out first by dividing the colour by the albedo, leaving the
+// lighting, which is smoother, and multiplied back in afterwards, so it keeps
+// its detail however strongly the lighting is filtered.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub struct Denoiser {
+    // Pixels out to this many either side are averaged in.
+    radius: usize,
+    sigma_spatial: f64,
+    sigma_albedo: f64,
+    // Of one minus the cosine between the normals.
+    sigma_normal: f64,
+    // Of the difference in depth, relative to the nearer of the two.
+    sigma_depth: f64,
+    // Of the difference in lighting, relative to its brightness, which keeps
+    // shadow edges; the filter's own first pass is used, as single pixels are
+    // too noisy to compare.
+    sigma_lighting: f64
+}
+
+impl Denoiser {
+    pub fn new() -> Denoiser {
+        Denoiser {
+            radius: 5,
+            sigma_spatial: 3.0,
+            sigma_albedo: 0.1,
+            sigma_normal: 0.1,
+            sigma_depth: 0.05,
+            sigma_lighting: 0.2
+        }
+    }
+
+    pub fn build(radius: usize, sigma_spatial: f64, sigma_albedo: f64, sigma_normal: f64, sigma_depth: f64, sigma_lighting: f64) -> Denoiser {
+        // A sigma of zero would weigh even the centre pixel by 0 * -inf, turning the image to NaN.
+        for sigma in [sigma_spatial, sigma_albedo, sigma_normal, sigma_depth, sigma_lighting] {
+            assert!(sigma > 0.0 && sigma.is_finite(), "Denoiser: sigmas must be greater than zero and finite, not {}.", sigma);
+        }
+
+        Denoiser {
+            radius,
+            sigma_spatial,
+            sigma_albedo,
+            sigma_normal,
+            sigma_depth,
+            sigma_lighting
+        }
+    }
+
+    pub fn denoise(& self, framebuffer: & Framebuffer, aovs: & Aovs) -> Framebuffer {
+        let albedo = aovs.albedo.pixels();
+        let lighting: Vec<Colour> = framebuffer.pixels().iter().zip(albedo)
+            .map(|(colour, albedo)| demodulate(colour, albedo))
+            .collect();
+
+        // The first pass only has the guides to go on; the second also compares
+        // the lighting the first found.
+        let guide = self.filter(framebuffer, &lighting, aovs, None);
+        let filtered = self.filter(framebuffer, &lighting, aovs, Some(&guide));
+
+        let mut result = Framebuffer::new(framebuffer.width(), framebuffer.height());
+        for y in 0..framebuffer.height() {
+            let start = y * framebuffer.width();
+            for (x, pixel) in result.row_mut(y).iter_mut().enumerate() {
+                *pixel = remodulate(&filtered[start + x], &albedo[start + x]);
+            }
+        }
+        result
+    }
+
+    fn filter(& self, framebuffer: & Framebuffer, lighting: & [Colour], aovs: & Aovs, guide: Option<& [Colour]>) -> Vec<Colour> {
+        let (width, height) = (framebuffer.width(), framebuffer.height());
+        let (albedo, normal, depth) = (aovs.albedo.pixels(), aovs.normal.pixels(), aovs.depth.pixels());
+        let radius = self.radius as isize;
+
+        let spatial_scale = -0.5 / (self.sigma_spatial * self.sigma_spatial);
+        let albedo_scale = -0.5 / (self.sigma_albedo * self.sigma_albedo);
+        let normal_scale = -0.5 / (self.sigma_normal * self.sigma_normal);
+        let depth_scale = -0.5 / (self.sigma_depth * self.sigma_depth);
+        let lighting_scale = -0.5 / (self.sigma_lighting * self.sigma_lighting);
+
+        let mut filtered = Vec::with_capacity(width * height);
+        for y in 0..height as isize {
+            for x in 0..width as isize {
+                let centre = y as usize * width + x as usize;
+                let mut sum = Colour::new();
+                let mut total_weight = 0.0;
+
+                for dy in -radius..=radius {
+                    for dx in -radius..=radius {
+                        let (nx, ny) = (x + dx, y + dy);
+                        if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
+                            continue
+                        }
+                        let neighbour = ny as usize * width + nx as usize;
+
+                        let mut exponent = spatial_scale * (dx * dx + dy * dy) as f64
+                            + albedo_scale * (albedo[centre] - albedo[neighbour]).length_squared()
+                            + normal_scale * squared(1.0 - dot_product(&normal[centre], &normal[neighbour]));
+
+                        let (depth_centre, depth_neighbour) = (depth[centre].x(), depth[neighbour].x());
+                        let nearer = depth_centre.min(depth_neighbour);
+                        if nearer > 0.0 {
+                            exponent += depth_scale * squared((depth_centre - depth_neighbour) / nearer);
+                        } else if depth_centre != depth_neighbour {
+                            // One of them missed everything.
+                            continue
+                        }
+
+                        if let Some(guide) = guide {
+                            let brightness = luminance(&guide[centre]).max(luminance(&guide[neighbour])).max(ALBEDO_EPSILON);
+                            exponent += lighting_scale * squared(luminance(&(guide[centre] - guide[neighbour])) / brightness);
+                        }
+
+                        let weight = exponent.exp();
+                        sum += lighting[neighbour] * weight;
+                        total_weight += weight;
+                    }
+                }
+
+                filtered.push(sum / total_weight);
+            }
+        }
+        filtered
+    }
+}
+
+impl Default for Denoiser {
+    fn default() -> Denoiser {
+        Denoiser::new()
+    }
+}
+
+fn squared(value: f64) -> f64 {
+    value * value
+}
+
+fn demodulate(colour: & Colour, albedo: & Colour) -> Colour {
+    let channel = |c: f64, a: f64| c / (a + ALBEDO_EPSILON);
+    Colour::build(channel(colour.x(), albedo.x()), channel(colour.y(), albedo.y()), channel(colour.z(), albedo.z()))
+}
+
+fn remodulate(lighting: & Colour, albedo: & Colour) -> Colour {
+    let channel = |l: f64, a: f64| l * (a + ALBEDO_EPSILON);
+    Colour::build(channel(lighting.x(), albedo.x()), channel(lighting.y(), albedo.y()), channel(lighting.z(), albedo.z()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::common::vec3::Point3;
+    use crate::engine::camera::Camera;
+    use crate::materials::MatArc;
+    use crate::materials::lambertian::Lambertian;
+    use crate::primitive::hittable_list::HittableList;
+    use crate::primitive::sphere::Sphere;
+
+    use super::*;
+
+    fn camera(samples_per_pixel: i32) -> Camera {
+        let mut camera = Camera::new();
+        camera.set_image_size(Some(24), Some(24));
+        camera.set_samples_per_pixel(samples_per_pixel);
+        camera.set_thread_count(1);
+        camera.set_seed(7);
+        camera.initialize();
+        camera
+    }
+
+    fn mean_squared_error(a: & Framebuffer, b: & Framebuffer) -> f64 {
+        let total: f64 = a.pixels().iter().zip(b.pixels()).map(|(a, b)| (*a - *b).length_squared()).sum();
+        total / (3 * a.pixels().len()) as f64
+    }
+
+    #[test]
+    fn denoising_brings_a_noisy_render_closer_to_a_converged_one() {
+        let ground: MatArc = Arc::new(Box::new(Lambertian::build_explicit(0.5, 0.5, 0.5)));
+        let ball: MatArc = Arc::new(Box::new(Lambertian::build_explicit(0.7, 0.3, 0.3)));
+        let mut world = HittableList::new();
+        world.add(Sphere::build(&Point3::build(0.0, 100.5, -1.0), &100.0, &ground));
+        world.add(Sphere::build(&Point3::build(0.0, 0.0, -1.0), &0.5, &ball));
+        let lights = HittableList::new();
+
+        let reference = camera(1024).render(&world, &lights);
+        let (noisy, aovs) = camera(4).render_with_aovs(&world, &lights);
+        let denoised = Denoiser::new().denoise(&noisy, &aovs);
+
+        let (noisy_error, denoised_error) = (mean_squared_error(&noisy, &reference), mean_squared_error(&denoised, &reference));
+        assert!(denoised_error < noisy_error, "MSE went from {} to {}", noisy_error, denoised_error);
+    }
+
+    #[test]
+    #[should_panic(expected = "sigmas must be greater than zero")]
+    fn rejects_a_sigma_of_zero() {
+        Denoiser::build(5, 3.0, 0.1, 0.0, 0.05, 0.2);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::common::colour::Colour;
+
+// Linear, unclamped colours as they come out of the renderer, stored row-major
+// from the top-left pixel. Encoding happens only when the image is written out.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<Colour>
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![Colour::new(); width * height]
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixels(&self) -> & [Colour] {
+        &self.pixels
+    }
+
+    // Mutable access to one scanline, for filling it in a single pass.
+    pub fn row_mut(&mut self, y: usize) -> &mut [Colour] {
+        let start = self.index(0, y);
+        &mut self.pixels[start..start + self.width]
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        assert!(x < self.width && y < self.height, "Framebuffer: pixel ({}, {}) out of bounds.", x, y);
+        y * self.width + x
+    }
+}
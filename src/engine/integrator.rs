@@ -0,0 +1,468 @@
+use crate::common::colour::Colour;
+use crate::common::interval::Interval;
+use crate::common::mat3::Matrix3;
+use crate::common::random::{ random_f64_standard, Prng };
+use crate::common::ray::Ray;
+use crate::common::vec3::random_cosine_direction;
+use crate::common::RAY_MINIMUM_DISTANCE_BEFORE_HIT;
+
+use crate::primitive::hittable::{ HitRecord, Hittable };
+use crate::primitive::hittable_list::HittableList;
+
+use super::camera::{ Background, DEFAULT_ROULETTE_DEPTH };
+
+// Works out how much light comes back along a camera ray. The camera calls it
+// once per sample and averages the results into the pixel.
+pub trait Integrator: Send + Sync {
+    // `lights` are the objects that can be sampled directly; they are in `world` too.
+    fn ray_colour(
+        & self,
+        ray: Ray,
+        world: & dyn Hittable,
+        lights: & HittableList,
+        background: & Background,
+        rng: &mut Prng
+    ) -> Colour;
+}
+
+fn first_hit(ray: & Ray, world: & dyn Hittable, rng: &mut Prng) -> Option<HitRecord> {
+    match world.hit(ray, &Interval::build(RAY_MINIMUM_DISTANCE_BEFORE_HIT, f64::INFINITY), rng) {
+        (true, Some(hit_rec)) => Some(hit_rec),
+        _ => None
+    }
+}
+
+// How much of a sample found with one strategy to keep, when another could have
+// found it too (Veach's power heuristic). Both densities are for the same direction.
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (squared, other_squared) = (pdf * pdf, other_pdf * other_pdf);
+    if squared + other_squared <= 0.0 {
+        return 0.0
+    }
+    squared / (squared + other_squared)
+}
+
+// Light arriving at a hit straight from a point picked on one of the lights,
+// through the material, along with the densities with which light sampling and
+// the material would have picked that direction. None if nothing got through.
+fn sample_lights(
+    ray: & Ray,
+    hit_rec: & HitRecord,
+    world: & dyn Hittable,
+    lights: & HittableList,
+    rng: &mut Prng
+) -> Option<(Colour, f64, f64)> {
+    if lights.is_empty() {
+        return None
+    }
+
+    let direction = lights.random_direction(&hit_rec.point(), ray.time(), rng);
+    let light_ray = Ray::from(hit_rec.point(), direction).with_time(ray.time());
+    let light_pdf = lights.pdf_value(&light_ray, rng);
+    if light_pdf <= 0.0 {
+        return None
+    }
+
+    let (bsdf, bsdf_pdf) = hit_rec.material.evaluate(ray, hit_rec, &direction);
+    if bsdf.near_zero() {
+        return None
+    }
+
+    // Whatever surface the ray reaches first is what lights the hit, so other
+    // objects cast shadows. Volumes on the way only dim it, by how much light
+    // gets through them.
+    let light_rec = match world.surface_hit(&light_ray, &Interval::build(RAY_MINIMUM_DISTANCE_BEFORE_HIT, f64::INFINITY), rng) {
+        (true, Some(light_rec)) => light_rec,
+        _ => return None
+    };
+    let transmittance = world.transmittance(&light_ray, &Interval::build(RAY_MINIMUM_DISTANCE_BEFORE_HIT, light_rec.t), rng);
+    if transmittance <= 0.0 {
+        return None
+    }
+
+    let emitted = light_rec.material.emitted(&light_rec);
+    Some((emitted * bsdf * transmittance / light_pdf, light_pdf, bsdf_pdf))
+}
+
+
+// Unidirectional path tracing with light sampling at every diffuse bounce,
+// combined with BSDF sampling by multiple importance sampling. Follows one
+// path from the camera, bounce by bounce, carrying how much of the light found
+// further along still reaches the camera in `throughput`. After
+// `roulette_depth` bounces a path carries on with probability equal to its
+// brightest throughput channel, and survivors are scaled up to make up for the
+// ones cut short, so dim paths end early without darkening the image.
+pub struct PathTracer {
+    max_depth: i32,
+    roulette_depth: i32
+}
+
+impl PathTracer {
+    pub fn new() -> PathTracer {
+        PathTracer {
+            max_depth: 10,
+            roulette_depth: DEFAULT_ROULETTE_DEPTH
+        }
+    }
+
+    pub fn build(max_depth: i32, roulette_depth: i32) -> PathTracer {
+        PathTracer {
+            max_depth: max_depth.max(1),
+            roulette_depth: roulette_depth.max(0)
+        }
+    }
+}
+
+impl Default for PathTracer {
+    fn default() -> PathTracer {
+        PathTracer::new()
+    }
+}
+
+impl Integrator for PathTracer {
+    fn ray_colour(
+        & self,
+        ray: Ray,
+        world: & dyn Hittable,
+        lights: & HittableList,
+        background: & Background,
+        rng: &mut Prng
+    ) -> Colour {
+        let mut ray = ray;
+        let mut radiance = Colour::new();
+        let mut throughput = Colour::build(1.0, 1.0, 1.0);
+        // The density with which the last bounce picked `ray`, or None if nothing
+        // else could have picked it (camera rays and specular bounces).
+        let mut scatter_pdf: Option<f64> = None;
+
+        for depth in 0..self.max_depth {
+            let hit_rec = match first_hit(&ray, world, rng) {
+                Some(hit_rec) => hit_rec,
+                None => {
+                    radiance += throughput * background.colour(&ray);
+                    break
+                }
+            };
+
+            // Lights found by following the BSDF could also have been found by sampling
+            // them at the last bounce, so this only keeps its share.
+            let mut emitted = hit_rec.material.emitted(&hit_rec);
+            if let Some(pdf) = scatter_pdf {
+                if !emitted.near_zero() {
+                    emitted = emitted * power_heuristic(pdf, lights.pdf_value(&ray, rng));
+                }
+            }
+            radiance += throughput * emitted;
+
+            let scatter_rec = match hit_rec.material.scatter(&ray, &hit_rec, rng) {
+                (true, Some(scatter_rec)) => scatter_rec,
+                _ => break
+            };
+
+            if scatter_rec.specular {
+                throughput = throughput * scatter_rec.bsdf;
+                scatter_pdf = None;
+            } else {
+                if let Some((direct, light_pdf, bsdf_pdf)) = sample_lights(&ray, &hit_rec, world, lights, rng) {
+                    radiance += throughput * direct * power_heuristic(light_pdf, bsdf_pdf);
+                }
+                throughput = throughput * scatter_rec.bsdf / scatter_rec.pdf;
+                scatter_pdf = Some(scatter_rec.pdf);
+            }
+            ray = scatter_rec.ray;
+
+            if depth + 1 >= self.roulette_depth {
+                let survival = throughput.x().max(throughput.y()).max(throughput.z()).min(1.0);
+                if random_f64_standard(rng) >= survival {
+                    break
+                }
+                throughput /= survival;
+            }
+        }
+
+        radiance
+    }
+}
+
+
+// Whitted-style ray tracing: mirrors and glass are followed, but diffuse
+// surfaces only see the lights directly, through one shadow ray each. Quick,
+// and without the noise of indirect light, but also without its brightness.
+pub struct Whitted {
+    max_depth: i32
+}
+
+impl Whitted {
+    pub fn new() -> Whitted {
+        Whitted {
+            max_depth: 10
+        }
+    }
+
+    pub fn build(max_depth: i32) -> Whitted {
+        Whitted {
+            max_depth: max_depth.max(1)
+        }
+    }
+}
+
+impl Default for Whitted {
+    fn default() -> Whitted {
+        Whitted::new()
+    }
+}
+
+impl Integrator for Whitted {
+    fn ray_colour(
+        & self,
+        ray: Ray,
+        world: & dyn Hittable,
+        lights: & HittableList,
+        background: & Background,
+        rng: &mut Prng
+    ) -> Colour {
+        let mut ray = ray;
+        let mut radiance = Colour::new();
+        let mut throughput = Colour::build(1.0, 1.0, 1.0);
+
+        for _ in 0..self.max_depth {
+            let hit_rec = match first_hit(&ray, world, rng) {
+                Some(hit_rec) => hit_rec,
+                None => {
+                    radiance += throughput * background.colour(&ray);
+                    break
+                }
+            };
+            radiance += throughput * hit_rec.material.emitted(&hit_rec);
+
+            match hit_rec.material.scatter(&ray, &hit_rec, rng) {
+                (true, Some(scatter_rec)) if scatter_rec.specular => {
+                    throughput = throughput * scatter_rec.bsdf;
+                    ray = scatter_rec.ray;
+                }
+                (true, Some(_)) => {
+                    if let Some((direct, _, _)) = sample_lights(&ray, &hit_rec, world, lights, rng) {
+                        radiance += throughput * direct;
+                    }
+                    break
+                }
+                _ => break
+            }
+        }
+
+        radiance
+    }
+}
+
+
+// Shows the outward surface normal at the first hit, each component mapped
+// from [-1, 1] to [0, 1], as in the tutorial before materials.
+pub struct NormalShader {}
+
+impl NormalShader {
+    pub fn new() -> NormalShader {
+        NormalShader {}
+    }
+}
+
+impl Default for NormalShader {
+    fn default() -> NormalShader {
+        NormalShader::new()
+    }
+}
+
+impl Integrator for NormalShader {
+    fn ray_colour(
+        & self,
+        ray: Ray,
+        world: & dyn Hittable,
+        _lights: & HittableList,
+        background: & Background,
+        rng: &mut Prng
+    ) -> Colour {
+        match first_hit(&ray, world, rng) {
+            Some(hit_rec) => {
+                // Hit normals face the ray; turn back the ones seen from inside.
+                let normal = if hit_rec.front_face() { hit_rec.normal() } else { -hit_rec.normal() };
+                (normal + Colour::build(1.0, 1.0, 1.0)) * 0.5
+            }
+            None => background.colour(&ray)
+        }
+    }
+}
+
+
+// Ambient occlusion: white where the first hit can see the open sky, darker
+// where other surfaces within `distance` hide it. One cosine-weighted ray is
+// sent per sample, so the camera's samples per pixel set the quality.
+pub struct AmbientOcclusion {
+    distance: f64
+}
+
+impl AmbientOcclusion {
+    // Anything in the way counts, however far off.
+    pub fn new() -> AmbientOcclusion {
+        AmbientOcclusion {
+            distance: f64::INFINITY
+        }
+    }
+
+    pub fn build(distance: f64) -> AmbientOcclusion {
+        AmbientOcclusion {
+            distance
+        }
+    }
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> AmbientOcclusion {
+        AmbientOcclusion::new()
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn ray_colour(
+        & self,
+        ray: Ray,
+        world: & dyn Hittable,
+        _lights: & HittableList,
+        _background: & Background,
+        rng: &mut Prng
+    ) -> Colour {
+        let hit_rec = match first_hit(&ray, world, rng) {
+            Some(hit_rec) => hit_rec,
+            None => return Colour::build(1.0, 1.0, 1.0)
+        };
+
+        let frame = Matrix3::orthonormal_basis(&hit_rec.normal());
+        let direction = frame.to_world(&random_cosine_direction(rng));
+        let occlusion_ray = Ray::build(&hit_rec.point(), &direction).with_time(ray.time());
+
+        // Directions are unit length, so t is the distance.
+        match world.hit(&occlusion_ray, &Interval::build(RAY_MINIMUM_DISTANCE_BEFORE_HIT, self.distance), rng) {
+            (true, _) => Colour::new(),
+            _ => Colour::build(1.0, 1.0, 1.0)
+        }
+    }
+}
+
+
+// Shows the distance along the camera ray to the first hit. With a
+// `max_distance`, it is mapped from white up close to black at that distance
+// and beyond, for viewing as an image. Without one the distance itself is
+// written, for the floating point formats; misses are 0 either way.
+pub struct DepthShader {
+    max_distance: Option<f64>
+}
+
+impl DepthShader {
+    pub fn new() -> DepthShader {
+        DepthShader {
+            max_distance: None
+        }
+    }
+
+    pub fn build(max_distance: f64) -> DepthShader {
+        DepthShader {
+            max_distance: Some(max_distance)
+        }
+    }
+}
+
+impl Default for DepthShader {
+    fn default() -> DepthShader {
+        DepthShader::new()
+    }
+}
+
+impl Integrator for DepthShader {
+    fn ray_colour(
+        & self,
+        ray: Ray,
+        world: & dyn Hittable,
+        _lights: & HittableList,
+        _background: & Background,
+        rng: &mut Prng
+    ) -> Colour {
+        let hit_rec = match first_hit(&ray, world, rng) {
+            Some(hit_rec) => hit_rec,
+            None => return Colour::new()
+        };
+
+        // Camera rays aren't unit length, so t has to be scaled into a distance.
+        let distance = hit_rec.t * ray.direction().length();
+        let value = match self.max_distance {
+            Some(max_distance) => (1.0 - distance / max_distance).max(0.0),
+            None => distance
+        };
+        Colour::build(value, value, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::engine::camera::Camera;
+    use crate::engine::framebuffer::Framebuffer;
+    use crate::materials::MatArc;
+    use crate::materials::diffuse_light::DiffuseLight;
+    use crate::materials::lambertian::Lambertian;
+    use crate::primitive::sphere::Sphere;
+
+    use super::*;
+
+    const MAX_DEPTH: i32 = 20;
+    const RENDERS: usize = 12;
+
+    // A light and a ball inside a closed, bright sphere, so paths bounce for long.
+    fn render(roulette_depth: i32, seed: u64) -> Framebuffer {
+        let walls: MatArc = Arc::new(Box::new(Lambertian::build_explicit(0.8, 0.8, 0.8)));
+        let ball: MatArc = Arc::new(Box::new(Lambertian::build_explicit(0.7, 0.3, 0.3)));
+        let light: MatArc = Arc::new(Box::new(DiffuseLight::build_explicit(4.0, 4.0, 4.0)));
+
+        let mut world = HittableList::new();
+        world.add(Sphere::build_explicit((0.0, 0.0, 0.0), 4.0, &walls));
+        world.add(Sphere::build_explicit((0.0, -1.0, -2.0), 1.0, &ball));
+        world.add(Sphere::build_explicit((0.0, 2.0, -1.0), 0.5, &light));
+        let lights = HittableList::build(Sphere::build_explicit((0.0, 2.0, -1.0), 0.5, &light));
+
+        let mut camera = Camera::new();
+        camera.set_image_size(Some(6), Some(6));
+        camera.set_samples_per_pixel(32);
+        camera.set_max_depth(MAX_DEPTH);
+        camera.set_roulette_depth(roulette_depth);
+        camera.set_background(Background::Solid(Colour::new()));
+        camera.set_thread_count(1);
+        camera.set_seed(seed);
+        camera.initialize();
+        camera.render(&world, &lights)
+    }
+
+    // Per-pixel, per-channel mean over the renders, and its standard error.
+    fn statistics(renders: & [Framebuffer]) -> Vec<(f64, f64)> {
+        let n = renders.len() as f64;
+        (0..renders[0].pixels().len() * 3).map(|i| {
+            let values: Vec<f64> = renders.iter().map(|render| render.pixels()[i / 3][i % 3]).collect();
+            let mean = values.iter().sum::<f64>() / n;
+            let variance = values.iter().map(|value| (value - mean) * (value - mean)).sum::<f64>() / (n - 1.0);
+            (mean, (variance / n).sqrt())
+        }).collect()
+    }
+
+    #[test]
+    fn russian_roulette_is_unbiased() {
+        let roulette: Vec<Framebuffer> = (0..RENDERS as u64).map(|seed| render(0, seed)).collect();
+        let fixed: Vec<Framebuffer> = (0..RENDERS as u64).map(|seed| render(MAX_DEPTH, seed)).collect();
+        let changed = roulette[0].pixels().iter().zip(fixed[0].pixels()).any(|(a, b)| (*a - *b).length() > 0.0);
+        assert!(changed, "roulette should change the paths taken");
+
+        for ((roulette_mean, roulette_error), (fixed_mean, fixed_error)) in statistics(&roulette).into_iter().zip(statistics(&fixed)) {
+            let error = (roulette_error * roulette_error + fixed_error * fixed_error).sqrt();
+            assert!(
+                (roulette_mean - fixed_mean).abs() <= 4.0 * error,
+                "means differ: {} with roulette, {} without (standard error {})", roulette_mean, fixed_mean, error
+            );
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use std::fs::File;
+use std::io::{ self, BufWriter, Write };
+use std::path::Path;
+
+use crate::common::colour::{ colour_to_bytes, Colour };
+
+use super::framebuffer::Framebuffer;
+
+const PPM_FORMAT : &str = "P6";
+const PPM_MAX_COLOUR : i32 = 255;
+
+const EXR_MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+const EXR_VERSION: u32 = 2;
+const EXR_PIXEL_TYPE_FLOAT: i32 = 2;
+
+// New-style RLE scanlines are only allowed for widths in this range.
+const RGBE_RLE_MIN_WIDTH: usize = 8;
+const RGBE_RLE_MAX_WIDTH: usize = 0x7fff;
+const RGBE_MIN_RUN: usize = 4;
+const RGBE_MAX_RUN: usize = 127;
+const RGBE_MAX_LITERAL: usize = 128;
+// Just under 2^127, the most the largest exponent can hold.
+const RGBE_MAX_VALUE: f64 = 1.7e38;
+
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Ppm,
+    // The floating point formats keep the linear colour, unclamped and without gamma.
+    Hdr,
+    Pfm,
+    Exr
+}
+
+impl ImageFormat {
+    // Picks the format from the file extension, if it is one we can write.
+    pub fn from_path(path: & Path) -> Option<ImageFormat> {
+        ImageFormat::from_extension(path.extension()?.to_str()?)
+    }
+
+    // Also accepts format names given without a path, e.g. "exr" or "PNG".
+    pub fn from_extension(extension: &str) -> Option<ImageFormat> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(ImageFormat::Png),
+            "ppm" => Some(ImageFormat::Ppm),
+            "hdr" => Some(ImageFormat::Hdr),
+            "pfm" => Some(ImageFormat::Pfm),
+            "exr" => Some(ImageFormat::Exr),
+            _ => None
+        }
+    }
+}
+
+// Every format needs at least one pixel, and the rows are split by width.
+fn check_size(framebuffer: & Framebuffer) -> io::Result<()> {
+    if framebuffer.width() == 0 || framebuffer.height() == 0 {
+        let message = format!("can't write a {}x{} image", framebuffer.width(), framebuffer.height());
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, message))
+    }
+    Ok(())
+}
+
+// 8-bit gamma-encoded RGB triples, row by row.
+fn to_rgb8(framebuffer: & Framebuffer) -> Vec<u8> {
+    framebuffer.pixels().iter().flat_map(colour_to_bytes).collect()
+}
+
+pub fn write_image(framebuffer: & Framebuffer, path: & Path, format: ImageFormat) -> io::Result<()> {
+    match format {
+        ImageFormat::Png => write_png(framebuffer, path),
+        ImageFormat::Ppm => write_ppm(framebuffer, path),
+        ImageFormat::Hdr => write_hdr(framebuffer, path),
+        ImageFormat::Pfm => write_pfm(framebuffer, path),
+        ImageFormat::Exr => write_exr(framebuffer, path)
+    }
+}
+
+pub fn write_png(framebuffer: & Framebuffer, path: & Path) -> io::Result<()> {
+    check_size(framebuffer)?;
+    let writer = BufWriter::new(File::create(path)?);
+
+    let mut encoder = png::Encoder::new(writer, framebuffer.width() as u32, framebuffer.height() as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(io::Error::other)?;
+    writer.write_image_data(&to_rgb8(framebuffer)).map_err(io::Error::other)?;
+    writer.finish().map_err(io::Error::other)
+}
+
+// Binary (P6) PPM.
+pub fn write_ppm(framebuffer: & Framebuffer, path: & Path) -> io::Result<()> {
+    check_size(framebuffer)?;
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    write!(writer, "{}\n{} {}\n{}\n", PPM_FORMAT, framebuffer.width(), framebuffer.height(), PPM_MAX_COLOUR)?;
+    writer.write_all(&to_rgb8(framebuffer))?;
+    writer.flush()
+}
+
+// Radiance RGBE (.hdr), run-length encoded where the width allows it.
+pub fn write_hdr(framebuffer: & Framebuffer, path: & Path) -> io::Result<()> {
+    check_size(framebuffer)?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    let width = framebuffer.width();
+
+    write!(writer, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", framebuffer.height(), width)?;
+
+    for row in framebuffer.pixels().chunks(width) {
+        let rgbe: Vec<[u8; 4]> = row.iter().map(colour_to_rgbe).collect();
+
+        if !(RGBE_RLE_MIN_WIDTH..=RGBE_RLE_MAX_WIDTH).contains(&width) {
+            for pixel in &rgbe {
+                writer.write_all(pixel)?;
+            }
+            continue;
+        }
+
+        writer.write_all(&[2, 2, (width >> 8) as u8, (width & 0xff) as u8])?;
+        for channel in 0..4 {
+            let values: Vec<u8> = rgbe.iter().map(|pixel| pixel[channel]).collect();
+            write_rle_channel(&mut writer, &values)?;
+        }
+    }
+
+    writer.flush()
+}
+
+// Shared exponent encoding: the largest channel sets the exponent, and each
+// channel keeps 8 bits of mantissa relative to it.
+fn colour_to_rgbe(colour: & Colour) -> [u8; 4] {
+    let (r, g, b) = (rgbe_channel(colour.x()), rgbe_channel(colour.y()), rgbe_channel(colour.z()));
+    let brightest = r.max(g).max(b);
+
+    if brightest < 1e-32 {
+        return [0, 0, 0, 0]
+    }
+
+    // brightest = mantissa * 2^exponent, with mantissa in [0.5, 1).
+    let exponent = brightest.log2().floor() as i32 + 1;
+    let scale = 256.0 / f64::powi(2.0, exponent);
+
+    [
+        (r * scale).min(255.0) as u8,
+        (g * scale).min(255.0) as u8,
+        (b * scale).min(255.0) as u8,
+        (exponent + 128) as u8
+    ]
+}
+
+// RGBE has no negatives or non-finite values: NaN and negatives become 0,
+// and infinities the brightest value it can hold.
+fn rgbe_channel(value: f64) -> f64 {
+    if value.is_nan() {
+        return 0.0
+    }
+    value.clamp(0.0, RGBE_MAX_VALUE)
+}
+
+fn write_rle_channel(writer: &mut impl Write, values: & [u8]) -> io::Result<()> {
+    let mut i = 0;
+    while i < values.len() {
+        let run = run_length_at(values, i);
+        if run >= RGBE_MIN_RUN {
+            writer.write_all(&[128 + run as u8, values[i]])?;
+            i += run;
+            continue;
+        }
+
+        // Gather literals up to the next run worth encoding.
+        let start = i;
+        while i < values.len() && i - start < RGBE_MAX_LITERAL && run_length_at(values, i) < RGBE_MIN_RUN {
+            i += 1;
+        }
+        writer.write_all(&[(i - start) as u8])?;
+        writer.write_all(&values[start..i])?;
+    }
+
+    Ok(())
+}
+
+fn run_length_at(values: & [u8], start: usize) -> usize {
+    values[start..].iter()
+        .take(RGBE_MAX_RUN)
+        .take_while(|&&value| value == values[start])
+        .count()
+}
+
+// Portable float map: little-endian 32-bit floats, rows stored bottom to top.
+pub fn write_pfm(framebuffer: & Framebuffer, path: & Path) -> io::Result<()> {
+    check_size(framebuffer)?;
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    // A negative scale marks the data as little-endian.
+    write!(writer, "PF\n{} {}\n-1.0\n", framebuffer.width(), framebuffer.height())?;
+
+    for row in framebuffer.pixels().chunks(framebuffer.width()).rev() {
+        for pixel in row {
+            for channel in 0..3 {
+                writer.write_all(&(pixel[channel] as f32).to_le_bytes())?;
+            }
+        }
+    }
+
+    writer.flush()
+}
+
+// OpenEXR, single part, uncompressed scanlines of 32-bit float B, G, R channels.
+pub fn write_exr(framebuffer: & Framebuffer, path: & Path) -> io::Result<()> {
+    check_size(framebuffer)?;
+
+    let width = framebuffer.width();
+    let height = framebuffer.height();
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&EXR_MAGIC);
+    header.extend_from_slice(&EXR_VERSION.to_le_bytes());
+
+    // Channels must be listed in alphabetical order.
+    let mut channels = Vec::new();
+    for name in ["B", "G", "R"] {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&EXR_PIXEL_TYPE_FLOAT.to_le_bytes());
+        // pLinear, three reserved bytes, then x and y sampling.
+        channels.extend_from_slice(&[0, 0, 0, 0]);
+        channels.extend_from_slice(&1i32.to_le_bytes());
+        channels.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channels.push(0);
+    push_exr_attribute(&mut header, "channels", "chlist", &channels);
+
+    push_exr_attribute(&mut header, "compression", "compression", &[0]);
+
+    let mut window = Vec::new();
+    for value in [0, 0, width as i32 - 1, height as i32 - 1] {
+        window.extend_from_slice(&i32::to_le_bytes(value));
+    }
+    push_exr_attribute(&mut header, "dataWindow", "box2i", &window);
+    push_exr_attribute(&mut header, "displayWindow", "box2i", &window);
+
+    push_exr_attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    push_exr_attribute(&mut header, "pixelAspectRatio", "float", &1.0f32.to_le_bytes());
+    push_exr_attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    push_exr_attribute(&mut header, "screenWindowWidth", "float", &1.0f32.to_le_bytes());
+    header.push(0);
+
+    // One scanline per chunk: its y coordinate, its byte count, then the data.
+    let line_bytes = width * 3 * 4;
+    let chunk_bytes = 8 + line_bytes;
+    let table_end = header.len() + height * 8;
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&header)?;
+    for y in 0..height {
+        writer.write_all(&((table_end + y * chunk_bytes) as u64).to_le_bytes())?;
+    }
+
+    for (y, row) in framebuffer.pixels().chunks(width).enumerate() {
+        writer.write_all(&(y as i32).to_le_bytes())?;
+        writer.write_all(&(line_bytes as i32).to_le_bytes())?;
+        for channel in [2, 1, 0] {
+            for pixel in row {
+                writer.write_all(&(pixel[channel] as f32).to_le_bytes())?;
+            }
+        }
+    }
+
+    writer.flush()
+}
+
+fn push_exr_attribute(header: &mut Vec<u8>, name: &str, type_name: &str, value: & [u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(type_name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn rgbe_survives_non_finite_colours() {
+        assert_eq!(colour_to_rgbe(&Colour::build(f64::NAN, -1.0, 0.0)), [0, 0, 0, 0]);
+        assert_eq!(colour_to_rgbe(&Colour::build(f64::INFINITY, 0.0, 0.0)), [255, 0, 0, 255]);
+        assert_eq!(colour_to_rgbe(&Colour::build(1.0, f64::NAN, f64::NEG_INFINITY)), [128, 0, 0, 129]);
+    }
+
+    #[test]
+    fn rejects_empty_images() {
+        let path = env::temp_dir().join("rtow_rust_empty_image");
+        for (width, height) in [(0, 4), (4, 0)] {
+            let framebuffer = Framebuffer::new(width, height);
+            for format in [ImageFormat::Png, ImageFormat::Ppm, ImageFormat::Hdr, ImageFormat::Pfm, ImageFormat::Exr] {
+                let error = write_image(&framebuffer, &path, format).expect_err("an empty image can't be written");
+                assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+            }
+        }
+    }
+}
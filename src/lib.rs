@@ -0,0 +1,33 @@
+//! A path tracer following the Ray Tracing in One Weekend series.
+//!
+//! `common` holds the maths, `primitive` the geometry, `materials` how surfaces
+//! scatter light, `textures` what colour they are where, and `engine` the
+//! camera, integrators and image writers that turn a world into a picture.
+//! `scene` loads whole scenes from TOML, and `presets` builds the ones the
+//! `rtow_rust` binary ships with.
+//!
+//! A minimal render looks like:
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! use rtow_rust::engine::output::{ write_image, ImageFormat };
+//! use rtow_rust::primitive::bvh::BvhNode;
+//! use rtow_rust::primitive::hittable_list::HittableList;
+//! use rtow_rust::presets;
+//!
+//! let mut scene = presets::three_spheres();
+//! let world = HittableList::build(BvhNode::from(scene.world));
+//!
+//! scene.camera.initialize();
+//! let framebuffer = scene.camera.render(&world, &scene.lights);
+//! write_image(&framebuffer, Path::new("image.png"), ImageFormat::Png).unwrap();
+//! ```
+
+pub mod common;
+pub mod primitive;
+pub mod engine;
+pub mod materials;
+pub mod textures;
+pub mod presets;
+pub mod scene;
@@ -1,115 +1,230 @@
-use std::f64::consts::PI;
-use std::rc::Rc;
-
-use common::colour::Colour;
-use common::interval::Interval;
-use common::random::{random_f64, random_f64_standard};
-use common::ray::Ray;
-use common::vec3::Point3;
-
-use engine::camera::Camera;
-
-use materials::dielectric::Dialectric;
-use materials::lambertian::Lambertian;
-use materials::metal::Metal;
-use materials::{MatRc, Material};
-
-use primitive::hittable::Hittable;
-use primitive::hittable_list::HittableList;
-use primitive::sphere::Sphere;
-
-mod common;
-mod primitive;
-mod engine;
-mod materials;
-
-
-// WINDOW
-const ASPECT_RATIO : f64 = 16.0 / 9.0;
-const IMAGE_WIDTH : i32 = 1200;
-const SAMPLES_PER_PIXEL: i32 = 500;
-const MAX_DEPTH: i32 = 50;
-
-// CAMERA
-const CAMERA_VERTICAL_FOV: f64 = 20.0;
-const CAMERA_DEFOCUS_ANGLE: f64 = 0.6;
-const CAMERA_FOCUS_DIST: f64 = 10.0;
-
-// MATERIAL THRESHOLDS FOR FINAL SCENE
-const DIFFUSE: f64 = 0.8;
-const METAL: f64 = 0.95;
-
-fn ray_colour(ray: & Ray, world: & dyn Hittable) -> Colour{
-    let (hit_anything, hit_rec) = world.hit(ray, &Interval::build(0.0, f64::INFINITY));
-
-    if hit_anything {
-        let normal = hit_rec.expect("ray_colour: Hit reported, but hit record missing.")
-                                .normal.expect("hit record normal missing when requested.");
-        return (Colour::build(1.0, 1.0, 1.0) + normal) * 0.5;
+use std::path::{ Path, PathBuf };
+use std::process;
+
+use clap::{ Parser, ValueEnum };
+
+use rtow_rust::engine::adaptive::{ heatmap, AdaptiveSampling };
+use rtow_rust::engine::denoiser::Denoiser;
+use rtow_rust::engine::framebuffer::Framebuffer;
+use rtow_rust::engine::integrator::{ AmbientOcclusion, DepthShader, Integrator, NormalShader, Whitted };
+use rtow_rust::engine::output::{ write_image, ImageFormat };
+use rtow_rust::presets;
+use rtow_rust::primitive::bvh::BvhNode;
+use rtow_rust::primitive::hittable_list::HittableList;
+use rtow_rust::scene::Scene;
+
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Preset {
+    RandomSpheres,
+    ThreeSpheres,
+    TexturedSpheres,
+    CornellBox,
+    CornellSmoke
+}
+
+impl Preset {
+    fn scene(&self) -> Scene {
+        match self {
+            Preset::RandomSpheres => presets::random_spheres(),
+            Preset::ThreeSpheres => presets::three_spheres(),
+            Preset::TexturedSpheres => presets::textured_spheres(),
+            Preset::CornellBox => presets::cornell_box(),
+            Preset::CornellSmoke => presets::cornell_smoke()
+        }
     }
+}
 
-    let unit_direction = common::vec3::unit_vector(ray.direction());
-    let a = 0.5 * (unit_direction.y() + 1.0);
-    Colour::build(1.0, 1.0, 1.0) * (1.0 - a) + Colour::build(0.5, 0.7, 1.0) * (a)
+#[derive(Clone, Copy, ValueEnum)]
+enum IntegratorChoice {
+    Path,
+    Whitted,
+    Normals,
+    AmbientOcclusion,
+    Depth
+}
+
+impl IntegratorChoice {
+    // None is the camera's own path tracer.
+    fn integrator(&self, max_depth: i32) -> Option<Box<dyn Integrator>> {
+        match self {
+            IntegratorChoice::Path => None,
+            IntegratorChoice::Whitted => Some(Box::new(Whitted::build(max_depth))),
+            IntegratorChoice::Normals => Some(Box::new(NormalShader::new())),
+            IntegratorChoice::AmbientOcclusion => Some(Box::new(AmbientOcclusion::new())),
+            IntegratorChoice::Depth => Some(Box::new(DepthShader::new()))
+        }
+    }
+}
+
+/// Renders a scene file or one of the built-in scenes to an image.
+///
+/// Options given here override the scene's own camera settings.
+#[derive(Parser)]
+#[command(version)]
+struct Args {
+    /// TOML scene description to render
+    #[arg(conflicts_with = "preset")]
+    scene: Option<PathBuf>,
+
+    /// Built-in scene to render when no scene file is given
+    #[arg(short, long, value_enum, default_value_t = Preset::RandomSpheres)]
+    preset: Preset,
+
+    /// Image to write
+    #[arg(short, long, default_value = "image.png")]
+    output: PathBuf,
+
+    /// Image format, instead of guessing it from the output extension [png, ppm, hdr, pfm, exr]
+    #[arg(short, long, value_parser = parse_format)]
+    format: Option<ImageFormat>,
+
+    /// Image width in pixels
+    #[arg(short = 'W', long, value_parser = clap::value_parser!(i32).range(1..))]
+    width: Option<i32>,
+
+    /// Image height in pixels; with --width this overrides the aspect ratio
+    #[arg(short = 'H', long, value_parser = clap::value_parser!(i32).range(1..))]
+    height: Option<i32>,
+
+    /// Samples per pixel
+    #[arg(short, long, value_parser = clap::value_parser!(i32).range(1..))]
+    samples: Option<i32>,
+
+    /// Maximum number of bounces per path
+    #[arg(short = 'd', long, value_parser = clap::value_parser!(i32).range(1..))]
+    max_depth: Option<i32>,
+
+    /// Bounces before paths may be ended at random; set it to the maximum depth to turn this off
+    #[arg(long, value_parser = clap::value_parser!(i32).range(0..))]
+    roulette_depth: Option<i32>,
+
+    /// Also write the first-hit normal, albedo, depth, position, material ID and object ID
+    /// next to the image, e.g. image.normal.exr; use a floating point format to keep them exact
+    #[arg(long)]
+    aovs: bool,
+
+    /// Stop sampling a pixel once the standard error of its displayed brightness (0 to 1) falls
+    /// below this, e.g. 0.01; --samples is then the most a pixel gets
+    #[arg(long, value_parser = parse_threshold)]
+    adaptive: Option<f64>,
+
+    /// Samples every pixel gets before adaptive sampling may stop [default: 16]
+    #[arg(long, requires = "adaptive", value_parser = clap::value_parser!(i32).range(2..))]
+    min_samples: Option<i32>,
+
+    /// Also write how many samples each pixel took as a heatmap, e.g. image.samples.png
+    #[arg(long)]
+    heatmap: bool,
+
+    /// Filter out noise once the image is rendered, guided by the albedo, normals and depth
+    #[arg(long)]
+    denoise: bool,
+
+    /// How to work out the colour of each sample, instead of the scene's choice
+    #[arg(short, long, value_enum)]
+    integrator: Option<IntegratorChoice>,
+
+    /// Seed for the sample sequence; the same seed gives the same image
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Number of render threads [default: available parallelism]
+    #[arg(short = 'j', long, value_parser = clap::value_parser!(u64).range(1..))]
+    threads: Option<u64>
+}
+
+fn parse_format(name: &str) -> Result<ImageFormat, String> {
+    ImageFormat::from_extension(name).ok_or(format!("unknown image format '{}'", name))
+}
+
+fn parse_threshold(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(threshold) if threshold > 0.0 && threshold.is_finite() => Ok(threshold),
+        Ok(_) => Err("must be greater than zero".to_string()),
+        Err(error) => Err(error.to_string())
+    }
 }
 
 fn main() {
-    let mut world = HittableList::new();
-
-    let ground_material: MatRc  = Rc::new(Box::from(Lambertian::from(Colour::build(0.5, 0.5, 0.5))));
-    world.add(Sphere::build_explicit((0.0, -1000.0, 0.0), 1000.0, &ground_material));
-
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat = random_f64_standard();
-            let center = Point3::build(a as f64 + 0.9 * random_f64_standard(), 0.2, b as f64 + 0.9 * random_f64_standard());
-
-            if (center - Point3::build(4.0, 0.2, 0.0)).length() > 0.9 {
-                if choose_mat < DIFFUSE {
-                    let albedo = Colour::random_standard() * Colour::random_standard();
-                    let sphere_material: MatRc = Rc::new(Box::from(Lambertian::from(albedo)));
-                    world.add(Sphere::build(&center, &0.2, &sphere_material));
-                } else if choose_mat < METAL {
-                    let albedo = Colour::random(0.5, 1.0);
-                    let fuzz = random_f64(0.0, 0.5);
-                    let sphere_material: MatRc = Rc::new(Box::from(Metal::from(albedo, fuzz)));
-                    world.add(Sphere::build(&center, &0.2, &sphere_material));
-                } else {
-                    let albedo = Colour::new();
-                    let sphere_material: MatRc = Rc::new(Box::from(Dialectric::from(albedo, 1.5)));
-                    world.add(Sphere::build(&center, &0.2, &sphere_material));
-                }
-            }
+    let args = Args::parse();
+
+    let Scene { mut camera, world, lights, warnings } = match &args.scene {
+        Some(scene_path) => Scene::load(scene_path).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(1);
+        }),
+        None => args.preset.scene()
+    };
+    for warning in &warnings {
+        eprintln!("warning: {}", warning);
+    }
+
+    let format = match args.format.or(ImageFormat::from_path(&args.output)) {
+        Some(format) => format,
+        None => {
+            eprintln!("{}: can't tell the image format from the extension, use --format", args.output.display());
+            process::exit(1);
         }
+    };
+
+    camera.set_image_size(args.width, args.height);
+    if let Some(samples) = args.samples {
+        camera.set_samples_per_pixel(samples);
+    }
+    if let Some(max_depth) = args.max_depth {
+        camera.set_max_depth(max_depth);
+    }
+    if let Some(roulette_depth) = args.roulette_depth {
+        camera.set_roulette_depth(roulette_depth);
+    }
+    if let Some(integrator) = args.integrator {
+        camera.set_integrator(integrator.integrator(camera.max_depth()));
+    }
+    if let Some(threshold) = args.adaptive {
+        let defaults = AdaptiveSampling::new();
+        camera.set_adaptive(Some(AdaptiveSampling::build(args.min_samples.unwrap_or(defaults.min_samples()), threshold)));
+    }
+    if args.denoise {
+        camera.set_denoiser(Some(Denoiser::new()));
+    }
+    if let Some(seed) = args.seed {
+        camera.set_seed(seed);
+    }
+    if let Some(threads) = args.threads {
+        camera.set_thread_count(threads as usize);
     }
 
-    let material_1: MatRc = Rc::new(Box::from(Dialectric::from(Colour::new(), 1.5)));
-    world.add(Sphere::build_explicit((0.0, 1.0, 0.0), 1.0, &material_1));
-    
-    let material_2: MatRc = Rc::new(Box::from(Lambertian::from(Colour::build(0.4, 0.2, 0.1))));
-    world.add(Sphere::build_explicit((-4.0, 1.0, 0.0), 1.0, &material_2));
-    
-    let material_3: MatRc = Rc::new(Box::from(Metal::from(Colour::build(0.7, 0.6, 0.5), 0.0)));
-    world.add(Sphere::build_explicit((4.0, 1.0, 0.0), 1.0, &material_3));
-
-    // Camera.
-    let camera_lookfrom = Point3::build(13.0, 2.0, 3.0);
-    let camera_lookat = Point3::build(0.0, 0.0, 0.0);
-    let camera_vup = Point3::build(0.0, 1.0, 0.0);
-
-    let mut camera = Camera::build(
-        ASPECT_RATIO, 
-        IMAGE_WIDTH, 
-        SAMPLES_PER_PIXEL,
-        MAX_DEPTH,
-        CAMERA_VERTICAL_FOV,
-        camera_lookfrom,
-        camera_lookat,
-        camera_vup,
-        CAMERA_DEFOCUS_ANGLE,
-        CAMERA_FOCUS_DIST
-    );
+    let world = HittableList::build(BvhNode::from(world));
 
     camera.initialize();
-    camera.render(&world);
+    let output = camera.render_passes(&world, &lights, args.aovs);
+
+    write_or_exit(&output.image, &args.output, format);
+    // AOVs may also have been recorded for the denoiser, but they're only written if asked for.
+    if let (true, Some(aovs)) = (args.aovs, &output.aovs) {
+        for (name, buffer) in aovs.buffers() {
+            write_or_exit(buffer, &pass_path(&args.output, name), format);
+        }
+    }
+    if args.heatmap {
+        write_or_exit(&heatmap(&output.sample_counts), &pass_path(&args.output, "samples"), format);
+    }
+}
+
+fn write_or_exit(framebuffer: & Framebuffer, path: & Path, format: ImageFormat) {
+    if let Err(error) = write_image(framebuffer, path, format) {
+        eprintln!("{}: {}", path.display(), error);
+        process::exit(1);
+    }
+}
+
+// image.exr's normals go to image.normal.exr, and so on.
+fn pass_path(output: & Path, name: &str) -> PathBuf {
+    let stem = output.file_stem().map_or("image".into(), |stem| stem.to_string_lossy());
+    let file_name = match output.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, name, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, name)
+    };
+    output.with_file_name(file_name)
 }
@@ -1,18 +1,54 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
-use crate::common::{ colour::Colour, ray::Ray }; 
+use crate::common::{ colour::Colour, random::Prng, ray::Ray, vec3::Vector3 }; 
 use crate::primitive::hittable::HitRecord;
 
 pub mod lambertian;
 pub mod metal;
 pub mod dielectric;
+pub mod diffuse_light;
+pub mod isotropic;
+pub mod henyey_greenstein;
 
-pub type MatRc = Rc<Box<dyn Material>>;
+// Materials are shared between the objects of a scene and across render threads.
+pub type MatArc = Arc<Box<dyn Material>>;
 
-pub trait Material {
+// A direction scatter picked, and how much of the light arriving along it
+// carries on back along the incoming ray.
+pub struct ScatterRecord {
+    pub ray: Ray,
+    // The BSDF times the cosine of the new direction with the normal, or the
+    // phase function in a volume, so a path's weight goes up by bsdf / pdf.
+    pub bsdf: Colour,
+    // Density of having picked this direction, per unit solid angle.
+    pub pdf: f64,
+    // Scattered into a single direction, like a mirror, so no other way of
+    // picking directions could find it. bsdf is then the weight itself and pdf is 1.
+    pub specular: bool
+}
+
+pub trait Material: Send + Sync {
     fn scatter(
         &self,
         ray_in: & Ray,
-        hit_rec: & HitRecord
-    ) -> (bool, Colour, Ray);
+        hit_rec: & HitRecord,
+        rng: &mut Prng
+    ) -> (bool, Option<ScatterRecord>);
+
+    // The bsdf and pdf scatter would give `direction` had it picked it, e.g. for
+    // a direction picked towards a light instead. Specular materials give zeros.
+    fn evaluate(&self, _ray_in: & Ray, _hit_rec: & HitRecord, _direction: & Vector3) -> (Colour, f64) {
+        (Colour::new(), 0.0)
+    }
+
+    // The surface's colour at the hit, for the albedo AOV. Black for materials
+    // that have none, like lights.
+    fn albedo(&self, _hit_rec: & HitRecord) -> Colour {
+        Colour::new()
+    }
+
+    // Light given off by the surface itself. Most materials don't emit any.
+    fn emitted(&self, _hit_rec: & HitRecord) -> Colour {
+        Colour::new()
+    }
 }
\ No newline at end of file
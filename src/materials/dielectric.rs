@@ -1,45 +1,96 @@
 use crate::common::colour::Colour;
+use crate::common::random::{random_f64_standard, Prng};
 use crate::common::ray::Ray;
 use crate::common::vec3::{ dot_product, reflect, refract, unit_vector };
 
 use crate::primitive::hittable::HitRecord;
 
-use super::Material;
+use crate::textures::{ solid_texture, TexArc };
+
+use super::{ Material, ScatterRecord };
 
 pub struct Dialectric {
-    albedo: Colour,
-    refractive_index: f64
+    // Tints everything passing through or reflecting off the surface.
+    albedo: TexArc,
+    refractive_index: f64,
+    // Beer–Lambert absorption coefficient per unit distance travelled inside,
+    // per channel. Zero is perfectly clear.
+    absorption: Colour
 }
 
 
 impl Dialectric {
     pub fn new() -> Dialectric {
         Dialectric {
-            albedo: Colour::build(0.5, 0.5, 0.5),
-            refractive_index: 0.5
+            albedo: solid_texture(Colour::build(0.5, 0.5, 0.5)),
+            refractive_index: 0.5,
+            absorption: Colour::new()
         }
     }
 
     pub fn build(colour: Colour, refractive_index: f64) -> Dialectric {
         Dialectric {
-            albedo: colour.clone(),
-            refractive_index
+            albedo: solid_texture(colour),
+            refractive_index,
+            absorption: Colour::new()
         }
     }
 
     pub fn build_explicit(r: f64, g: f64, b: f64, refractive_index: f64) -> Dialectric {
         Dialectric {
-            albedo: Colour::build(r, g, b),
-            refractive_index
+            albedo: solid_texture(Colour::build(r, g, b)),
+            refractive_index,
+            absorption: Colour::new()
         }
     }
 
     pub fn from(colour: Colour, refractive_index: f64) -> Dialectric {
         Dialectric {
-            albedo: colour,
-            refractive_index
+            albedo: solid_texture(colour),
+            refractive_index,
+            absorption: Colour::new()
+        }
+    }
+
+    pub fn from_texture(texture: TexArc, refractive_index: f64) -> Dialectric {
+        Dialectric {
+            albedo: texture,
+            refractive_index,
+            absorption: Colour::new()
         }
     }
+
+    pub fn with_absorption(mut self, absorption: Colour) -> Dialectric {
+        self.absorption = absorption;
+        self
+    }
+
+    // Light lost on the way to this hit, if the ray came from inside.
+    fn transmittance(&self, ray_in: & Ray, hit_rec: & HitRecord) -> Colour {
+        if hit_rec.front_face() {
+            return Colour::build(1.0, 1.0, 1.0)
+        }
+
+        let distance = hit_rec.t * ray_in.direction().length();
+        Colour::build(
+            f64::exp(-self.absorption.x() * distance),
+            f64::exp(-self.absorption.y() * distance),
+            f64::exp(-self.absorption.z() * distance)
+        )
+    }
+}
+
+impl Default for Dialectric {
+    fn default() -> Dialectric {
+        Dialectric::new()
+    }
+}
+
+// Schlick's approximation of the Fresnel reflectance.
+fn reflectance(cosine: f64, refractive_index_ratio: f64) -> f64 {
+    let r0 = (1.0 - refractive_index_ratio) / (1.0 + refractive_index_ratio);
+    let r0 = r0 * r0;
+    r0 + (1.0 - r0) * f64::powi(1.0 - cosine, 5)
 }
 
 
@@ -47,9 +98,10 @@ impl Material for Dialectric {
     fn scatter(
             &self,
             ray_in: & Ray,
-            hit_rec: & HitRecord
-        ) -> (bool, Colour, Ray) {
-        let attenuation = Colour::build(1.0, 1.0, 1.0);
+            hit_rec: & HitRecord,
+            rng: &mut Prng
+        ) -> (bool, Option<ScatterRecord>) {
+        let attenuation = self.albedo.value(hit_rec.u, hit_rec.v, &hit_rec.point()) * self.transmittance(ray_in, hit_rec);
         let normal = &hit_rec.normal();
 
         let refractive_index_ratio = 
@@ -60,10 +112,22 @@ impl Material for Dialectric {
         let cos_theta = dot_product(&-unit_direction, &hit_rec.normal()).min(1.0);
         let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
 
+        // Total internal reflection, or else reflect as often as Fresnel says.
         let cannot_refract = refractive_index_ratio * sin_theta > 1.0;
+        let reflects = cannot_refract || reflectance(cos_theta, refractive_index_ratio) > random_f64_standard(rng);
         let direction = 
-            if cannot_refract { reflect(&unit_direction, &normal) } else { refract(&unit_direction, &normal, refractive_index_ratio) };
+            if reflects { reflect(&unit_direction, normal) } else { refract(&unit_direction, normal, refractive_index_ratio) };
+
+        let scatter_record = ScatterRecord {
+            ray: Ray::from(hit_rec.point(), direction).with_time(ray_in.time()),
+            bsdf: attenuation,
+            pdf: 1.0,
+            specular: true
+        };
+        (true, Some(scatter_record))
+    }
 
-        (true, attenuation, Ray::from(hit_rec.point(), direction))
+    fn albedo(&self, hit_rec: & HitRecord) -> Colour {
+        self.albedo.value(hit_rec.u, hit_rec.v, &hit_rec.point())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,68 @@
+use crate::common::colour::Colour;
+use crate::common::random::Prng;
+use crate::common::ray::Ray;
+
+use crate::primitive::hittable::HitRecord;
+
+use crate::textures::{ solid_texture, TexArc };
+
+use super::{ Material, ScatterRecord };
+
+// Emits in every direction, with the radiance given by its texture, and
+// absorbs whatever lands on it.
+pub struct DiffuseLight {
+    emit: TexArc
+}
+
+impl DiffuseLight {
+    pub fn new() -> DiffuseLight {
+        DiffuseLight {
+            emit: solid_texture(Colour::build(1.0, 1.0, 1.0))
+        }
+    }
+
+    pub fn build(colour: Colour) -> DiffuseLight {
+        DiffuseLight {
+            emit: solid_texture(colour)
+        }
+    }
+
+    pub fn build_explicit(r: f64, g: f64, b: f64) -> DiffuseLight {
+        DiffuseLight {
+            emit: solid_texture(Colour::build(r, g, b))
+        }
+    }
+
+    pub fn from(colour: Colour) -> DiffuseLight {
+        DiffuseLight {
+            emit: solid_texture(colour)
+        }
+    }
+
+    pub fn from_texture(texture: TexArc) -> DiffuseLight {
+        DiffuseLight {
+            emit: texture
+        }
+    }
+}
+
+impl Default for DiffuseLight {
+    fn default() -> DiffuseLight {
+        DiffuseLight::new()
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+            &self,
+            _ray_in: & Ray,
+            _hit_rec: & HitRecord,
+            _rng: &mut Prng
+        ) -> (bool, Option<ScatterRecord>) {
+            (false, None)
+    }
+
+    fn emitted(&self, hit_rec: & HitRecord) -> Colour {
+        self.emit.value(hit_rec.u, hit_rec.v, &hit_rec.point())
+    }
+}
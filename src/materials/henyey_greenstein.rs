@@ -0,0 +1,107 @@
+use std::f64::consts::PI;
+
+use crate::common::colour::Colour;
+use crate::common::mat3::Matrix3;
+use crate::common::random::{random_f64_standard, Prng};
+use crate::common::ray::Ray;
+use crate::common::vec3::{ dot_product, unit_vector, Vector3 };
+
+use crate::primitive::hittable::HitRecord;
+
+use crate::textures::{ solid_texture, TexArc };
+
+use super::{ Material, ScatterRecord };
+
+// Below this |g| the phase function is sampled as isotropic, which the
+// Henyey–Greenstein inversion can't do without dividing by zero.
+const ISOTROPIC_CUTOFF: f64 = 1e-3;
+
+// Phase function for volumes that scatter mostly forwards (g > 0, like fog
+// and clouds) or backwards (g < 0). g is the mean cosine of the scattering angle.
+pub struct HenyeyGreenstein {
+    albedo: TexArc,
+    g: f64
+}
+
+impl HenyeyGreenstein {
+    pub fn new() -> HenyeyGreenstein {
+        HenyeyGreenstein {
+            albedo: solid_texture(Colour::build(0.5, 0.5, 0.5)),
+            g: 0.0
+        }
+    }
+
+    pub fn from(colour: Colour, g: f64) -> HenyeyGreenstein {
+        HenyeyGreenstein::from_texture(solid_texture(colour), g)
+    }
+
+    // g is kept strictly inside (-1, 1), where the distribution is defined.
+    pub fn from_texture(texture: TexArc, g: f64) -> HenyeyGreenstein {
+        HenyeyGreenstein {
+            albedo: texture,
+            g: g.clamp(-0.999, 0.999)
+        }
+    }
+
+    // Density per unit solid angle of scattering through an angle with this cosine.
+    fn phase(&self, cos_theta: f64) -> f64 {
+        let denominator = 1.0 + self.g * self.g - 2.0 * self.g * cos_theta;
+        (1.0 - self.g * self.g) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+
+    // Cosine of the angle between the incoming and scattered directions.
+    fn sample_cos_theta(&self, rng: &mut Prng) -> f64 {
+        let xi = random_f64_standard(rng);
+        if self.g.abs() < ISOTROPIC_CUTOFF {
+            return 1.0 - 2.0 * xi
+        }
+
+        let g = self.g;
+        let term = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+        ((1.0 + g * g - term * term) / (2.0 * g)).clamp(-1.0, 1.0)
+    }
+}
+
+impl Default for HenyeyGreenstein {
+    fn default() -> HenyeyGreenstein {
+        HenyeyGreenstein::new()
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+            &self,
+            ray_in: & Ray,
+            hit_rec: & HitRecord,
+            rng: &mut Prng
+        ) -> (bool, Option<ScatterRecord>) {
+            let cos_theta = self.sample_cos_theta(rng);
+            let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
+            let phi = 2.0 * PI * random_f64_standard(rng);
+
+            // Sampled around the direction the ray was already travelling in.
+            let frame = Matrix3::orthonormal_basis(&unit_vector(ray_in.direction()));
+            let local = Vector3::build(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+
+            let direction = frame.to_world(&local);
+
+            let (bsdf, pdf) = self.evaluate(ray_in, hit_rec, &direction);
+            let scatter_record = ScatterRecord {
+                ray: Ray::from(hit_rec.point(), direction).with_time(ray_in.time()),
+                bsdf,
+                pdf,
+                specular: false
+            };
+            (true, Some(scatter_record))
+    }
+
+    fn evaluate(&self, ray_in: & Ray, hit_rec: & HitRecord, direction: & Vector3) -> (Colour, f64) {
+        let cos_theta = dot_product(&unit_vector(ray_in.direction()), &unit_vector(direction));
+        let phase = self.phase(cos_theta);
+        (self.albedo.value(hit_rec.u, hit_rec.v, &hit_rec.point()) * phase, phase)
+    }
+
+    fn albedo(&self, hit_rec: & HitRecord) -> Colour {
+        self.albedo.value(hit_rec.u, hit_rec.v, &hit_rec.point())
+    }
+}
@@ -0,0 +1,71 @@
+use std::f64::consts::PI;
+
+use crate::common::colour::Colour;
+use crate::common::random::Prng;
+use crate::common::ray::Ray;
+use crate::common::vec3::{ random_unit_vector, Vector3 };
+
+use crate::primitive::hittable::HitRecord;
+
+use crate::textures::{ solid_texture, TexArc };
+
+use super::{ Material, ScatterRecord };
+
+// Phase function scattering equally in every direction, for volumes.
+pub struct Isotropic {
+    albedo: TexArc
+}
+
+impl Isotropic {
+    pub fn new() -> Isotropic {
+        Isotropic {
+            albedo: solid_texture(Colour::build(0.5, 0.5, 0.5))
+        }
+    }
+
+    pub fn from(colour: Colour) -> Isotropic {
+        Isotropic {
+            albedo: solid_texture(colour)
+        }
+    }
+
+    pub fn from_texture(texture: TexArc) -> Isotropic {
+        Isotropic {
+            albedo: texture
+        }
+    }
+}
+
+impl Default for Isotropic {
+    fn default() -> Isotropic {
+        Isotropic::new()
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+            &self,
+            ray_in: & Ray,
+            hit_rec: & HitRecord,
+            rng: &mut Prng
+        ) -> (bool, Option<ScatterRecord>) {
+            let direction = random_unit_vector(rng);
+            let (bsdf, pdf) = self.evaluate(ray_in, hit_rec, &direction);
+            let scatter_record = ScatterRecord {
+                ray: Ray::from(hit_rec.point(), direction).with_time(ray_in.time()),
+                bsdf,
+                pdf,
+                specular: false
+            };
+            (true, Some(scatter_record))
+    }
+
+    fn evaluate(&self, _ray_in: & Ray, hit_rec: & HitRecord, _direction: & Vector3) -> (Colour, f64) {
+        let phase = 1.0 / (4.0 * PI);
+        (self.albedo.value(hit_rec.u, hit_rec.v, &hit_rec.point()) * phase, phase)
+    }
+
+    fn albedo(&self, hit_rec: & HitRecord) -> Colour {
+        self.albedo.value(hit_rec.u, hit_rec.v, &hit_rec.point())
+    }
+}
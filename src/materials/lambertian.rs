@@ -1,54 +1,91 @@
+use std::f64::consts::PI;
+
 use crate::common::colour::Colour;
+use crate::common::mat3::Matrix3;
+use crate::common::random::Prng;
 use crate::common::ray::Ray;
-use crate::common::vec3::random_unit_vector;
+use crate::common::vec3::{ dot_product, random_cosine_direction, unit_vector, Vector3 };
 
 use crate::primitive::hittable::HitRecord;
 
-use super::Material;
+use crate::textures::{ solid_texture, TexArc };
+
+use super::{ Material, ScatterRecord };
 
 pub struct Lambertian {
-    albedo: Colour
+    albedo: TexArc
 }
 
 impl Lambertian {
     pub fn new() -> Lambertian {
         Lambertian {
-            albedo: Colour::build(0.5, 0.5, 0.5)
+            albedo: solid_texture(Colour::build(0.5, 0.5, 0.5))
         }
     }
 
     pub fn build(colour: Colour) -> Lambertian {
         Lambertian {
-            albedo: colour.clone()
+            albedo: solid_texture(colour)
         }
     }
 
     pub fn build_explicit(r: f64, g: f64, b: f64) -> Lambertian {
         Lambertian {
-            albedo: Colour::build(r, g, b)
+            albedo: solid_texture(Colour::build(r, g, b))
         }
     }
 
     pub fn from(colour: Colour) -> Lambertian {
         Lambertian {
-            albedo: colour
+            albedo: solid_texture(colour)
+        }
+    }
+
+    pub fn from_texture(texture: TexArc) -> Lambertian {
+        Lambertian {
+            albedo: texture
         }
     }
 }
 
+impl Default for Lambertian {
+    fn default() -> Lambertian {
+        Lambertian::new()
+    }
+}
+
 impl Material for Lambertian {
     fn scatter(
             &self,
             ray_in: & Ray,
-            hit_rec: & HitRecord
-        ) -> (bool, Colour, Ray) {
-            let mut scatter_direction = hit_rec.normal() + random_unit_vector();
+            hit_rec: & HitRecord,
+            rng: &mut Prng
+        ) -> (bool, Option<ScatterRecord>) {
+            // Cosine-weighted about the normal, which cancels the cosine in the BSDF.
+            let frame = Matrix3::orthonormal_basis(&hit_rec.normal());
+            let scatter_direction = frame.to_world(&random_cosine_direction(rng));
+
+            let (bsdf, pdf) = self.evaluate(ray_in, hit_rec, &scatter_direction);
+            let scatter_record = ScatterRecord {
+                ray: Ray::build(&hit_rec.point(), &scatter_direction).with_time(ray_in.time()),
+                bsdf,
+                pdf,
+                specular: false
+            };
+            (pdf > 0.0, Some(scatter_record))
+    }
 
-            if scatter_direction.near_zero() {
-                scatter_direction = hit_rec.normal()
-            }
+    fn evaluate(&self, _ray_in: & Ray, hit_rec: & HitRecord, direction: & Vector3) -> (Colour, f64) {
+        let cosine = dot_product(&hit_rec.normal(), &unit_vector(direction));
+        if cosine <= 0.0 {
+            return (Colour::new(), 0.0)
+        }
 
-            let scattered_ray = Ray::build(&hit_rec.point(), &scatter_direction);
-            (true, self.albedo, scattered_ray)
+        let albedo = self.albedo.value(hit_rec.u, hit_rec.v, &hit_rec.point());
+        (albedo * (cosine / PI), cosine / PI)
     }
-}
\ No newline at end of file
+
+    fn albedo(&self, hit_rec: & HitRecord) -> Colour {
+        self.albedo.value(hit_rec.u, hit_rec.v, &hit_rec.point())
+    }
+}
@@ -1,62 +1,86 @@
 use crate::common::colour::Colour; 
+use crate::common::random::Prng;
 use crate::common::ray::Ray;
 use crate::common::vec3::{ dot_product, random_unit_vector, reflect, unit_vector }; 
 
 use crate::primitive::hittable::HitRecord;
 
-use super::Material;
+use crate::textures::{ solid_texture, TexArc };
+
+use super::{ Material, ScatterRecord };
 
 pub struct Metal {
-    albedo: Colour,
+    albedo: TexArc,
     fuzz: f64
 }
 
 impl Metal {
     pub fn new() -> Metal {
         Metal {
-            albedo: Colour::build(0.5, 0.5, 0.5),
+            albedo: solid_texture(Colour::build(0.5, 0.5, 0.5)),
             fuzz: 0.5
         }
     }
 
     pub fn build(colour: Colour, fuzz: f64) -> Metal {
         Metal {
-            albedo: colour.clone(),
+            albedo: solid_texture(colour),
             fuzz
         }
     }
 
     pub fn build_explicit(r: f64, g: f64, b: f64, fuzz: f64) -> Metal {
         Metal {
-            albedo: Colour::build(r, g, b),
+            albedo: solid_texture(Colour::build(r, g, b)),
             fuzz
         }
     }
 
     pub fn from(colour: Colour, fuzz: f64) -> Metal {
         Metal {
-            albedo: colour,
+            albedo: solid_texture(colour),
+            fuzz
+        }
+    }
+
+    pub fn from_texture(texture: TexArc, fuzz: f64) -> Metal {
+        Metal {
+            albedo: texture,
             fuzz
         }
     }
 }
 
+impl Default for Metal {
+    fn default() -> Metal {
+        Metal::new()
+    }
+}
+
 impl Material for Metal {
     fn scatter(
             &self,
             ray_in: & Ray,
-            hit_rec: & HitRecord
-        ) -> (bool, Colour, Ray) {
+            hit_rec: & HitRecord,
+            rng: &mut Prng
+        ) -> (bool, Option<ScatterRecord>) {
             let reflect_direction = reflect(ray_in.direction(), &hit_rec.normal());
             // this is where we add the fuzz
             // i wonder the result of biasing the random vector's direction towards the reflectance direction?
-            let reflected = unit_vector(&reflect_direction) + (random_unit_vector() * self.fuzz);
-            let scattered_ray = Ray::build(&hit_rec.point(), &reflected);
+            let reflected = unit_vector(&reflect_direction) + (random_unit_vector(rng) * self.fuzz);
+            let scattered_ray = Ray::build(&hit_rec.point(), &reflected).with_time(ray_in.time());
 
-            (
-                dot_product(scattered_ray.direction(), &hit_rec.normal()) > 0.0, 
-                self.albedo, 
-                scattered_ray
-            )
+            let scatter_record = ScatterRecord {
+                bsdf: self.albedo.value(hit_rec.u, hit_rec.v, &hit_rec.point()),
+                pdf: 1.0,
+                // Fuzz blurs the reflection, but it's still treated as a single direction.
+                specular: true,
+                ray: scattered_ray
+            };
+            (dot_product(scatter_record.ray.direction(), &hit_rec.normal()) > 0.0, Some(scatter_record))
     }
-}
\ No newline at end of file
+
+    fn albedo(&self, hit_rec: & HitRecord) -> Colour {
+        self.albedo.value(hit_rec.u, hit_rec.v, &hit_rec.point())
+    }
+}
@@ -0,0 +1,252 @@
+use std::sync::Arc;
+
+use crate::common::colour::Colour;
+use crate::common::random::{random_f64, random_f64_standard, Prng};
+use crate::common::vec3::{ Point3, Vector3 };
+
+use crate::engine::camera::{ Background, Camera };
+
+use crate::materials::dielectric::Dialectric;
+use crate::materials::diffuse_light::DiffuseLight;
+use crate::materials::isotropic::Isotropic;
+use crate::materials::lambertian::Lambertian;
+use crate::materials::metal::Metal;
+use crate::materials::MatArc;
+
+use crate::primitive::constant_medium::ConstantMedium;
+use crate::primitive::hittable_list::HittableList;
+use crate::primitive::quad::{ make_box, Quad };
+use crate::primitive::sphere::Sphere;
+
+use crate::scene::Scene;
+
+use crate::textures::checker::Checker;
+use crate::textures::noise_texture::{ NoisePattern, NoiseTexture };
+use crate::textures::TexArc;
+
+// WINDOW
+const ASPECT_RATIO : f64 = 16.0 / 9.0;
+const IMAGE_WIDTH : i32 = 1200;
+const SAMPLES_PER_PIXEL: i32 = 500;
+const MAX_DEPTH: i32 = 50;
+
+// CAMERA
+const CAMERA_VERTICAL_FOV: f64 = 20.0;
+const CAMERA_DEFOCUS_ANGLE: f64 = 0.6;
+const CAMERA_FOCUS_DIST: f64 = 10.0;
+
+// SEEDING
+const SCENE_SEED: u64 = 0;
+
+// MATERIAL THRESHOLDS FOR FINAL SCENE
+const DIFFUSE: f64 = 0.8;
+const METAL: f64 = 0.95;
+
+// The final scene from the first book.
+pub fn random_spheres() -> Scene {
+    let mut rng = Prng::seed(SCENE_SEED);
+    let mut world = HittableList::new();
+
+    let ground_material: MatArc  = Arc::new(Box::from(Lambertian::from(Colour::build(0.5, 0.5, 0.5))));
+    world.add(Sphere::build_explicit((0.0, -1000.0, 0.0), 1000.0, &ground_material));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat = random_f64_standard(&mut rng);
+            let center = Point3::build(a as f64 + 0.9 * random_f64_standard(&mut rng), 0.2, b as f64 + 0.9 * random_f64_standard(&mut rng));
+
+            if (center - Point3::build(4.0, 0.2, 0.0)).length() > 0.9 {
+                if choose_mat < DIFFUSE {
+                    let albedo = Colour::random_standard(&mut rng) * Colour::random_standard(&mut rng);
+                    let sphere_material: MatArc = Arc::new(Box::from(Lambertian::from(albedo)));
+                    world.add(Sphere::build(&center, &0.2, &sphere_material));
+                } else if choose_mat < METAL {
+                    let albedo = Colour::random(&mut rng, 0.5, 1.0);
+                    let fuzz = random_f64(&mut rng, 0.0, 0.5);
+                    let sphere_material: MatArc = Arc::new(Box::from(Metal::from(albedo, fuzz)));
+                    world.add(Sphere::build(&center, &0.2, &sphere_material));
+                } else {
+                    let albedo = Colour::build(1.0, 1.0, 1.0);
+                    let sphere_material: MatArc = Arc::new(Box::from(Dialectric::from(albedo, 1.5)));
+                    world.add(Sphere::build(&center, &0.2, &sphere_material));
+                }
+            }
+        }
+    }
+
+    let material_1: MatArc = Arc::new(Box::from(Dialectric::from(Colour::build(1.0, 1.0, 1.0), 1.5)));
+    world.add(Sphere::build_explicit((0.0, 1.0, 0.0), 1.0, &material_1));
+    
+    let material_2: MatArc = Arc::new(Box::from(Lambertian::from(Colour::build(0.4, 0.2, 0.1))));
+    world.add(Sphere::build_explicit((-4.0, 1.0, 0.0), 1.0, &material_2));
+    
+    let material_3: MatArc = Arc::new(Box::from(Metal::from(Colour::build(0.7, 0.6, 0.5), 0.0)));
+    world.add(Sphere::build_explicit((4.0, 1.0, 0.0), 1.0, &material_3));
+
+    // Camera.
+    let camera_lookfrom = Point3::build(13.0, 2.0, 3.0);
+    let camera_lookat = Point3::build(0.0, 0.0, 0.0);
+    let camera_vup = Point3::build(0.0, 1.0, 0.0);
+
+    let camera = Camera::build(
+        ASPECT_RATIO, 
+        IMAGE_WIDTH, 
+        SAMPLES_PER_PIXEL,
+        MAX_DEPTH,
+        CAMERA_VERTICAL_FOV,
+        camera_lookfrom,
+        camera_lookat,
+        camera_vup,
+        CAMERA_DEFOCUS_ANGLE,
+        CAMERA_FOCUS_DIST
+    );
+
+    Scene { camera, world, lights: HittableList::new(), warnings: Vec::new() }
+}
+
+// A matte, a glass and a metal sphere side by side, as in the middle of the first book.
+pub fn three_spheres() -> Scene {
+    let mut world = HittableList::new();
+
+    let material_ground: MatArc = Arc::new(Box::from(Lambertian::from(Colour::build(0.8, 0.8, 0.0))));
+    let material_center: MatArc = Arc::new(Box::from(Lambertian::from(Colour::build(0.1, 0.2, 0.5))));
+    let material_left: MatArc = Arc::new(Box::from(Dialectric::from(Colour::build(1.0, 1.0, 1.0), 1.5)));
+    let material_bubble: MatArc = Arc::new(Box::from(Dialectric::from(Colour::build(1.0, 1.0, 1.0), 1.0 / 1.5)));
+    let material_right: MatArc = Arc::new(Box::from(Metal::from(Colour::build(0.8, 0.6, 0.2), 1.0)));
+
+    world.add(Sphere::build_explicit((0.0, -100.5, -1.0), 100.0, &material_ground));
+    world.add(Sphere::build_explicit((0.0, 0.0, -1.2), 0.5, &material_center));
+    world.add(Sphere::build_explicit((-1.0, 0.0, -1.0), 0.5, &material_left));
+    world.add(Sphere::build_explicit((-1.0, 0.0, -1.0), 0.4, &material_bubble));
+    world.add(Sphere::build_explicit((1.0, 0.0, -1.0), 0.5, &material_right));
+
+    let camera = Camera::build(
+        ASPECT_RATIO,
+        400,
+        100,
+        MAX_DEPTH,
+        90.0,
+        Point3::build(0.0, 0.0, 0.0),
+        Point3::build(0.0, 0.0, -1.0),
+        Vector3::build(0.0, 1.0, 0.0),
+        0.0,
+        1.0
+    );
+
+    Scene { camera, world, lights: HittableList::new(), warnings: Vec::new() }
+}
+
+// A checkered floor under spheres of marble and turbulent noise.
+pub fn textured_spheres() -> Scene {
+    let mut world = HittableList::new();
+
+    let checker: TexArc = Arc::new(Box::from(Checker::build_colours(0.32, Colour::build(0.2, 0.3, 0.1), Colour::build(0.9, 0.9, 0.9))));
+    let marble: TexArc = Arc::new(Box::from(NoiseTexture::from(4.0, Colour::build(1.0, 1.0, 1.0), NoisePattern::Marble, 0)));
+    let turbulence: TexArc = Arc::new(Box::from(NoiseTexture::from(4.0, Colour::build(0.9, 0.6, 0.3), NoisePattern::Turbulence, 1)));
+
+    let ground: MatArc = Arc::new(Box::from(Lambertian::from_texture(checker)));
+    let marble: MatArc = Arc::new(Box::from(Lambertian::from_texture(marble)));
+    let turbulence: MatArc = Arc::new(Box::from(Metal::from_texture(turbulence, 0.3)));
+
+    world.add(Sphere::build_explicit((0.0, -1000.0, 0.0), 1000.0, &ground));
+    world.add(Sphere::build_explicit((0.0, 2.0, -1.5), 2.0, &marble));
+    world.add(Sphere::build_explicit((0.5, 1.0, 2.0), 1.0, &turbulence));
+
+    let camera = Camera::build(
+        ASPECT_RATIO,
+        400,
+        100,
+        MAX_DEPTH,
+        CAMERA_VERTICAL_FOV,
+        Point3::build(13.0, 2.0, 3.0),
+        Point3::build(0.0, 1.0, 0.0),
+        Vector3::build(0.0, 1.0, 0.0),
+        0.0,
+        10.0
+    );
+
+    Scene { camera, world, lights: HittableList::new(), warnings: Vec::new() }
+}
+
+// The Cornell box from the second book, lit only by the ceiling light.
+pub fn cornell_box() -> Scene {
+    let mut world = HittableList::new();
+    let mut lights = HittableList::new();
+
+    let red: MatArc = Arc::new(Box::from(Lambertian::from(Colour::build(0.65, 0.05, 0.05))));
+    let white: MatArc = Arc::new(Box::from(Lambertian::from(Colour::build(0.73, 0.73, 0.73))));
+    let green: MatArc = Arc::new(Box::from(Lambertian::from(Colour::build(0.12, 0.45, 0.15))));
+    let light: MatArc = Arc::new(Box::from(DiffuseLight::from(Colour::build(15.0, 15.0, 15.0))));
+
+    world.add(Quad::build(&Point3::build(555.0, 0.0, 0.0), &Vector3::build(0.0, 555.0, 0.0), &Vector3::build(0.0, 0.0, 555.0), &green));
+    world.add(Quad::build(&Point3::build(0.0, 0.0, 0.0), &Vector3::build(0.0, 555.0, 0.0), &Vector3::build(0.0, 0.0, 555.0), &red));
+    let ceiling_light = Arc::new(Quad::build(&Point3::build(343.0, 554.0, 332.0), &Vector3::build(-130.0, 0.0, 0.0), &Vector3::build(0.0, 0.0, -105.0), &light));
+    world.add(Arc::clone(&ceiling_light));
+    lights.add(ceiling_light);
+    world.add(Quad::build(&Point3::build(0.0, 0.0, 0.0), &Vector3::build(555.0, 0.0, 0.0), &Vector3::build(0.0, 0.0, 555.0), &white));
+    world.add(Quad::build(&Point3::build(555.0, 555.0, 555.0), &Vector3::build(-555.0, 0.0, 0.0), &Vector3::build(0.0, 0.0, -555.0), &white));
+    world.add(Quad::build(&Point3::build(0.0, 0.0, 555.0), &Vector3::build(555.0, 0.0, 0.0), &Vector3::build(0.0, 555.0, 0.0), &white));
+
+    world.add(make_box(&Point3::build(265.0, 0.0, 295.0), &Point3::build(430.0, 330.0, 460.0), &white));
+    world.add(make_box(&Point3::build(130.0, 0.0, 65.0), &Point3::build(295.0, 165.0, 230.0), &white));
+
+    let mut camera = Camera::build(
+        1.0,
+        600,
+        200,
+        MAX_DEPTH,
+        40.0,
+        Point3::build(278.0, 278.0, -800.0),
+        Point3::build(278.0, 278.0, 0.0),
+        Vector3::build(0.0, 1.0, 0.0),
+        0.0,
+        10.0
+    );
+    camera.set_background(Background::Solid(Colour::new()));
+
+    Scene { camera, world, lights, warnings: Vec::new() }
+}
+
+// The Cornell box with its two blocks made of black and white smoke, under a larger, dimmer light.
+pub fn cornell_smoke() -> Scene {
+    let mut world = HittableList::new();
+    let mut lights = HittableList::new();
+
+    let red: MatArc = Arc::new(Box::from(Lambertian::from(Colour::build(0.65, 0.05, 0.05))));
+    let white: MatArc = Arc::new(Box::from(Lambertian::from(Colour::build(0.73, 0.73, 0.73))));
+    let green: MatArc = Arc::new(Box::from(Lambertian::from(Colour::build(0.12, 0.45, 0.15))));
+    let light: MatArc = Arc::new(Box::from(DiffuseLight::from(Colour::build(7.0, 7.0, 7.0))));
+
+    world.add(Quad::build(&Point3::build(555.0, 0.0, 0.0), &Vector3::build(0.0, 555.0, 0.0), &Vector3::build(0.0, 0.0, 555.0), &green));
+    world.add(Quad::build(&Point3::build(0.0, 0.0, 0.0), &Vector3::build(0.0, 555.0, 0.0), &Vector3::build(0.0, 0.0, 555.0), &red));
+    let ceiling_light = Arc::new(Quad::build(&Point3::build(113.0, 554.0, 127.0), &Vector3::build(330.0, 0.0, 0.0), &Vector3::build(0.0, 0.0, 305.0), &light));
+    world.add(Arc::clone(&ceiling_light));
+    lights.add(ceiling_light);
+    world.add(Quad::build(&Point3::build(0.0, 555.0, 0.0), &Vector3::build(555.0, 0.0, 0.0), &Vector3::build(0.0, 0.0, 555.0), &white));
+    world.add(Quad::build(&Point3::build(0.0, 0.0, 0.0), &Vector3::build(555.0, 0.0, 0.0), &Vector3::build(0.0, 0.0, 555.0), &white));
+    world.add(Quad::build(&Point3::build(0.0, 0.0, 555.0), &Vector3::build(555.0, 0.0, 0.0), &Vector3::build(0.0, 555.0, 0.0), &white));
+
+    let black_smoke: MatArc = Arc::new(Box::from(Isotropic::from(Colour::build(0.0, 0.0, 0.0))));
+    let white_smoke: MatArc = Arc::new(Box::from(Isotropic::from(Colour::build(1.0, 1.0, 1.0))));
+
+    let tall_box = make_box(&Point3::build(265.0, 0.0, 295.0), &Point3::build(430.0, 330.0, 460.0), &white);
+    let short_box = make_box(&Point3::build(130.0, 0.0, 65.0), &Point3::build(295.0, 165.0, 230.0), &white);
+    world.add(ConstantMedium::build(tall_box, 0.01, &black_smoke));
+    world.add(ConstantMedium::build(short_box, 0.01, &white_smoke));
+
+    let mut camera = Camera::build(
+        1.0,
+        600,
+        200,
+        MAX_DEPTH,
+        40.0,
+        Point3::build(278.0, 278.0, -800.0),
+        Point3::build(278.0, 278.0, 0.0),
+        Vector3::build(0.0, 1.0, 0.0),
+        0.0,
+        10.0
+    );
+    camera.set_background(Background::Solid(Colour::new()));
+
+    Scene { camera, world, lights, warnings: Vec::new() }
+}
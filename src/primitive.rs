@@ -1,3 +1,14 @@
+pub mod aabb;
+pub mod bvh;
 pub mod sphere;
+pub mod quad;
+pub mod triangle;
+pub mod triangle_mesh;
+pub mod obj;
+pub mod constant_medium;
+pub mod density_grid;
+pub mod grid_volume;
+pub mod transform;
+pub mod tagged;
 pub mod hittable;
 pub mod hittable_list;
\ No newline at end of file
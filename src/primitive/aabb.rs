@@ -0,0 +1,124 @@
+use crate::common::interval::{ Interval, EMPTY };
+use crate::common::ray::Ray;
+use crate::common::vec3::Point3;
+
+// Boxes thinner than this along an axis are padded, so that flat primitives
+// still produce a slab the ray can enter.
+const MINIMUM_BOX_WIDTH: f64 = 0.0001;
+
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub struct Aabb {
+    x: Interval,
+    y: Interval,
+    z: Interval
+}
+
+impl Aabb {
+    pub fn new() -> Aabb {
+        Aabb {
+            x: EMPTY,
+            y: EMPTY,
+            z: EMPTY
+        }
+    }
+
+    pub fn build(x: Interval, y: Interval, z: Interval) -> Aabb {
+        let mut bbox = Aabb { x, y, z };
+        bbox.pad_to_minimums();
+        bbox
+    }
+
+    // Treats the two points as opposite corners, in any order.
+    pub fn from_points(a: & Point3, b: & Point3) -> Aabb {
+        Aabb::build(
+            Interval::build(a.x().min(b.x()), a.x().max(b.x())),
+            Interval::build(a.y().min(b.y()), a.y().max(b.y())),
+            Interval::build(a.z().min(b.z()), a.z().max(b.z()))
+        )
+    }
+
+    pub fn enclosing(box0: & Aabb, box1: & Aabb) -> Aabb {
+        Aabb {
+            x: Interval::enclosing(&box0.x, &box1.x),
+            y: Interval::enclosing(&box0.y, &box1.y),
+            z: Interval::enclosing(&box0.z, &box1.z)
+        }
+    }
+
+    pub fn axis_interval(&self, n: usize) -> & Interval {
+        match n {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Aabb: axis {} out of range.", n)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.size() < 0.0 || self.y.size() < 0.0 || self.z.size() < 0.0
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        if self.x.size() > self.y.size() {
+            if self.x.size() > self.z.size() { 0 } else { 2 }
+        } else if self.y.size() > self.z.size() { 1 } else { 2 }
+    }
+
+    pub fn centroid(&self, axis: usize) -> f64 {
+        let interval = self.axis_interval(axis);
+        (interval.min() + interval.max()) * 0.5
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0
+        }
+
+        let (dx, dy, dz) = (self.x.size(), self.y.size(), self.z.size());
+        2.0 * (dx * dy + dy * dz + dz * dx)
+    }
+
+    // Slab test: clip the ray interval against each axis in turn.
+    pub fn hit(&self, ray: & Ray, ray_t: & Interval) -> bool {
+        self.clip(ray, ray_t).is_some()
+    }
+
+    // The part of ray_t during which the ray is inside the box, if any.
+    pub fn clip(&self, ray: & Ray, ray_t: & Interval) -> Option<Interval> {
+        let origin = ray.origin();
+        let direction = ray.direction();
+        let mut t_min = ray_t.min();
+        let mut t_max = ray_t.max();
+
+        for axis in 0..3 {
+            let interval = self.axis_interval(axis);
+            let inverse_direction = 1.0 / direction[axis];
+
+            let t0 = (interval.min() - origin[axis]) * inverse_direction;
+            let t1 = (interval.max() - origin[axis]) * inverse_direction;
+            let (near, far) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+
+            if near > t_min { t_min = near; }
+            if far < t_max { t_max = far; }
+
+            if t_max <= t_min {
+                return None
+            }
+        }
+
+        Some(Interval::build(t_min, t_max))
+    }
+
+    fn pad_to_minimums(&mut self) {
+        if self.x.size() < MINIMUM_BOX_WIDTH { self.x = self.x.expand(MINIMUM_BOX_WIDTH); }
+        if self.y.size() < MINIMUM_BOX_WIDTH { self.y = self.y.expand(MINIMUM_BOX_WIDTH); }
+        if self.z.size() < MINIMUM_BOX_WIDTH { self.z = self.z.expand(MINIMUM_BOX_WIDTH); }
+    }
+}
+
+impl Default for Aabb {
+    fn default() -> Aabb {
+        Aabb::new()
+    }
+}
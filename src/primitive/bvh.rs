@@ -0,0 +1,206 @@
+use crate::common::interval::Interval;
+use crate::common::random::Prng;
+use crate::common::ray::Ray;
+use crate::common::vec3::Point3;
+
+use super::aabb::Aabb;
+use super::hittable::{ HitRecord, Hittable };
+use super::hittable_list::HittableList;
+
+pub struct BvhNode<'a> {
+    left: Box<dyn Hittable + 'a>,
+    // Leaves holding a single object have no right child.
+    right: Option<Box<dyn Hittable + 'a>>,
+    bbox: Aabb
+}
+
+impl<'a> BvhNode<'a> {
+    pub fn from(list: HittableList<'a>) -> BvhNode<'a> {
+        BvhNode::build(list.into_objects())
+    }
+
+    pub fn build(mut objects: Vec<Box<dyn Hittable + 'a>>) -> BvhNode<'a> {
+        let bbox = objects.iter()
+            .fold(Aabb::new(), |bbox, object| Aabb::enclosing(&bbox, &object.bounding_box()));
+
+        match objects.len() {
+            // An empty world: an empty list, which nothing hits.
+            0 => BvhNode { left: Box::new(HittableList::new()), right: None, bbox },
+            1 => {
+                let left = objects.pop().expect("BvhNode: object count changed during build.");
+                BvhNode { left, right: None, bbox }
+            }
+            2 => {
+                let right = objects.pop().expect("BvhNode: object count changed during build.");
+                let left = objects.pop().expect("BvhNode: object count changed during build.");
+                BvhNode { left, right: Some(right), bbox }
+            }
+            _ => {
+                let split = sah_split(&mut objects);
+                let right_objects = objects.split_off(split);
+
+                BvhNode {
+                    left: Box::new(BvhNode::build(objects)),
+                    right: Some(Box::new(BvhNode::build(right_objects))),
+                    bbox
+                }
+            }
+        }
+    }
+}
+
+// Sorts the objects along the longest axis of their centroids and returns the
+// index to split them at, chosen to minimise the surface area heuristic.
+fn sah_split(objects: &mut [Box<dyn Hittable + '_>]) -> usize {
+    let centroid_bounds = objects.iter().fold(Aabb::new(), |bounds, object| {
+        let bbox = object.bounding_box();
+        let centroid = Point3::build(bbox.centroid(0), bbox.centroid(1), bbox.centroid(2));
+        Aabb::enclosing(&bounds, &Aabb::from_points(&centroid, &centroid))
+    });
+    let axis = centroid_bounds.longest_axis();
+
+    objects.sort_by(|a, b| {
+        a.bounding_box().centroid(axis).total_cmp(&b.bounding_box().centroid(axis))
+    });
+
+    let count = objects.len();
+    let boxes: Vec<Aabb> = objects.iter().map(|object| object.bounding_box()).collect();
+
+    // right_areas[i] is the area of the box around objects[i..].
+    let mut right_areas = vec![0.0; count];
+    let mut right_box = Aabb::new();
+    for i in (1..count).rev() {
+        right_box = Aabb::enclosing(&right_box, &boxes[i]);
+        right_areas[i] = right_box.surface_area();
+    }
+
+    let mut best_split = count / 2;
+    let mut best_cost = f64::INFINITY;
+    let mut left_box = Aabb::new();
+    for i in 1..count {
+        left_box = Aabb::enclosing(&left_box, &boxes[i - 1]);
+        let cost = left_box.surface_area() * i as f64 + right_areas[i] * (count - i) as f64;
+
+        if cost < best_cost {
+            best_cost = cost;
+            best_split = i;
+        }
+    }
+
+    best_split
+}
+
+impl BvhNode<'_> {
+    // The nearest hit in the subtree, or with `surfaces_only` the nearest surface.
+    fn closest_hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng, surfaces_only: bool) -> (bool, Option<HitRecord>) {
+        if !self.bbox.hit(ray, ray_t) {
+            return (false, None)
+        }
+
+        let find = |object: & dyn Hittable, ray_t: & Interval, rng: &mut Prng| {
+            if surfaces_only { object.surface_hit(ray, ray_t, rng) } else { object.hit(ray, ray_t, rng) }
+        };
+
+        let (hit_left, left_rec) = find(&*self.left, ray_t, rng);
+
+        let right = match &self.right {
+            Some(right) => right,
+            None => return (hit_left, left_rec)
+        };
+
+        let closest_t = match &left_rec {
+            Some(rec) if hit_left => rec.t,
+            _ => ray_t.max()
+        };
+        let (hit_right, right_rec) = find(&**right, &Interval::build(ray_t.min(), closest_t), rng);
+
+        if hit_right {
+            (true, right_rec)
+        } else {
+            (hit_left, left_rec)
+        }
+    }
+}
+
+impl Hittable for BvhNode<'_> {
+    fn hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        self.closest_hit(ray, ray_t, rng, false)
+    }
+
+    fn surface_hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        self.closest_hit(ray, ray_t, rng, true)
+    }
+
+    fn transmittance(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> f64 {
+        if !self.bbox.hit(ray, ray_t) {
+            return 1.0
+        }
+
+        let right = match &self.right {
+            Some(right) => right.transmittance(ray, ray_t, rng),
+            None => 1.0
+        };
+        self.left.transmittance(ray, ray_t, rng) * right
+    }
+
+    fn bounding_box(& self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::common::random::random_f64;
+    use crate::common::vec3::{ random_unit_vector, Vector3 };
+    use crate::materials::MatArc;
+    use crate::materials::lambertian::Lambertian;
+    use crate::primitive::sphere::Sphere;
+
+    use super::*;
+
+    fn random_spheres(rng: &mut Prng, material: & MatArc) -> HittableList<'static> {
+        let mut list = HittableList::new();
+        for _ in 0..200 {
+            let center = Point3::random(rng, -10.0, 10.0);
+            list.add(Sphere::build(&center, &random_f64(rng, 0.1, 1.5), material));
+        }
+        list
+    }
+
+    #[test]
+    fn hits_match_the_linear_list() {
+        let material: MatArc = Arc::new(Box::new(Lambertian::new()));
+        let list = random_spheres(&mut Prng::seed(1), &material);
+        let bvh = BvhNode::from(random_spheres(&mut Prng::seed(1), &material));
+
+        let mut rng = Prng::seed(2);
+        let ray_t = Interval::build(0.001, f64::INFINITY);
+        let mut hits = 0;
+        for _ in 0..5000 {
+            let ray = Ray::from(Point3::random(&mut rng, -15.0, 15.0), random_unit_vector(&mut rng));
+
+            let (list_hit, list_rec) = list.hit(&ray, &ray_t, &mut rng);
+            let (bvh_hit, bvh_rec) = bvh.hit(&ray, &ray_t, &mut rng);
+            assert_eq!(list_hit, bvh_hit);
+
+            if let (Some(list_rec), Some(bvh_rec)) = (list_rec.filter(|_| list_hit), bvh_rec.filter(|_| bvh_hit)) {
+                hits += 1;
+                assert!((list_rec.t - bvh_rec.t).abs() < 1e-9);
+                assert!((list_rec.point() - bvh_rec.point()).length() < 1e-9);
+                assert!((list_rec.normal() - bvh_rec.normal()).length() < 1e-9);
+            }
+        }
+        assert!(hits > 1000, "too few rays hit anything to compare: {}", hits);
+    }
+
+    #[test]
+    fn empty_world_is_never_hit() {
+        let bvh = BvhNode::from(HittableList::new());
+        let ray = Ray::from(Point3::new(), Vector3::build(0.0, 0.0, -1.0));
+
+        let (hit, _) = bvh.hit(&ray, &Interval::build(0.001, f64::INFINITY), &mut Prng::seed(0));
+        assert!(!hit);
+    }
+}
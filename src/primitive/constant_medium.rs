@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use crate::common::interval::{ Interval, UNIVERSE };
+use crate::common::random::{random_f64_standard, Prng};
+use crate::common::ray::Ray;
+use crate::common::vec3::Vector3;
+
+use crate::materials::MatArc;
+
+use super::aabb::Aabb;
+use super::hittable::{ HitRecord, Hittable };
+
+// Keeps the search for the exit from finding the entry again.
+const EXIT_SEARCH_OFFSET: f64 = 0.0001;
+
+// A volume of uniform density filling a closed boundary, like smoke or fog.
+// Rays pass through it or scatter somewhere inside, with the chance of getting
+// through falling off exponentially with the distance travelled. Where they
+// scatter, the phase function material (e.g. Isotropic) picks the new direction.
+pub struct ConstantMedium<'a> {
+    boundary: Box<dyn Hittable + 'a>,
+    negative_inverse_density: f64,
+    phase_function: MatArc
+}
+
+impl<'a> ConstantMedium<'a> {
+    pub fn build(boundary: impl Hittable + 'a, density: f64, phase_function: & MatArc) -> ConstantMedium<'a> {
+        ConstantMedium::from(Box::new(boundary), density, Arc::clone(phase_function))
+    }
+
+    pub fn from(boundary: Box<dyn Hittable + 'a>, density: f64, phase_function: MatArc) -> ConstantMedium<'a> {
+        // Zero would never scatter, and below it the free flights come out negative.
+        assert!(density > 0.0, "ConstantMedium: density must be greater than zero, not {}.", density);
+
+        ConstantMedium {
+            boundary,
+            negative_inverse_density: -1.0 / density,
+            phase_function
+        }
+    }
+}
+
+impl ConstantMedium<'_> {
+    // The stretch of ray_t the ray spends inside the boundary, as the t it
+    // enters and leaves at. Assumes a convex boundary.
+    fn inside(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> Option<(f64, f64)> {
+        let entry = match self.boundary.hit(ray, &UNIVERSE, rng) {
+            (true, Some(entry)) => entry,
+            _ => return None
+        };
+        let exit = match self.boundary.hit(ray, &Interval::build(entry.t + EXIT_SEARCH_OFFSET, f64::INFINITY), rng) {
+            (true, Some(exit)) => exit,
+            _ => return None
+        };
+
+        let entry_t = entry.t.max(ray_t.min()).max(0.0);
+        let exit_t = exit.t.min(ray_t.max());
+        if entry_t >= exit_t {
+            return None
+        }
+        Some((entry_t, exit_t))
+    }
+}
+
+impl Hittable for ConstantMedium<'_> {
+    // Finds where the ray is inside the boundary, then samples how far into
+    // that stretch it gets before scattering.
+    fn hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        let (entry_t, exit_t) = match self.inside(ray, ray_t, rng) {
+            Some(inside) => inside,
+            None => return (false, None)
+        };
+
+        let ray_length = ray.direction().length();
+        let distance_inside_boundary = (exit_t - entry_t) * ray_length;
+        // Exponentially distributed free-flight distance.
+        let hit_distance = self.negative_inverse_density * (1.0 - random_f64_standard(rng)).ln();
+        if hit_distance > distance_inside_boundary {
+            return (false, None)
+        }
+
+        let t = entry_t + hit_distance / ray_length;
+
+        // Scattering inside a volume has no surface, so the normal and side are arbitrary.
+        let hit_record = HitRecord {
+            t,
+            normal: Some(Vector3::build(1.0, 0.0, 0.0)),
+            material: Arc::clone(&self.phase_function),
+            point: ray.at(&t),
+            u: 0.0,
+            v: 0.0,
+            front_face: Some(true),
+            object_id: 0,
+            material_id: 0
+        };
+
+        (true, Some(hit_record))
+    }
+
+    fn bounding_box(& self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+
+    fn surface_hit(& self, _ray: & Ray, _ray_t: & Interval, _rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        (false, None)
+    }
+
+    // Exactly, as the density is the same throughout: Beer-Lambert over the distance inside.
+    fn transmittance(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> f64 {
+        match self.inside(ray, ray_t, rng) {
+            Some((entry_t, exit_t)) => ((exit_t - entry_t) * ray.direction().length() / self.negative_inverse_density).exp(),
+            None => 1.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::vec3::Point3;
+    use crate::materials::isotropic::Isotropic;
+    use crate::primitive::sphere::Sphere;
+
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "density must be greater than zero")]
+    fn rejects_a_density_of_zero() {
+        let phase_function: MatArc = Arc::new(Box::new(Isotropic::new()));
+        ConstantMedium::build(Sphere::build(&Point3::new(), &1.0, &phase_function), 0.0, &phase_function);
+    }
+}
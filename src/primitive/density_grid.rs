@@ -0,0 +1,178 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{ Path, PathBuf };
+
+use crate::common::noise::{ fbm, Perlin, DEFAULT_GAIN, DEFAULT_LACUNARITY };
+use crate::common::vec3::Point3;
+
+// How the voxels of a raw grid file are stored, all little-endian.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub enum SampleType {
+    // Scaled so 255 is a density of 1.
+    U8,
+    // Scaled so 65535 is a density of 1.
+    U16,
+    F32
+}
+
+impl SampleType {
+    fn size(& self) -> usize {
+        match self {
+            SampleType::U8 => 1,
+            SampleType::U16 => 2,
+            SampleType::F32 => 4
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GridError {
+    Io(PathBuf, io::Error),
+    Size { path: PathBuf, expected: usize, found: usize },
+    Resolution([usize; 3])
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            GridError::Size { path, expected, found } =>
+                write!(f, "{}: expected {} bytes for the given resolution and sample type, found {}", path.display(), expected, found),
+            GridError::Resolution(resolution) =>
+                write!(f, "grid resolution {:?} must be at least 1 along every axis", resolution)
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+// Densities sampled on a regular grid over the unit cube, x varying fastest,
+// then y, then z. Each value sits at the centre of its voxel and lookups
+// between them are trilinear. The largest value is kept as the majorant that
+// delta and ratio tracking sample against.
+pub struct DensityGrid {
+    resolution: [usize; 3],
+    values: Vec<f32>,
+    majorant: f64
+}
+
+impl DensityGrid {
+    pub fn from(resolution: [usize; 3], values: Vec<f32>) -> Result<DensityGrid, GridError> {
+        if resolution.contains(&0) {
+            return Err(GridError::Resolution(resolution))
+        }
+        assert_eq!(values.len(), resolution[0] * resolution[1] * resolution[2], "DensityGrid: value count doesn't match the resolution.");
+
+        // Negative densities have no meaning, and would break the tracking.
+        let values: Vec<f32> = values.into_iter().map(|value| value.max(0.0)).collect();
+        let majorant = values.iter().fold(0.0_f32, |max, &value| max.max(value)) as f64;
+
+        Ok(DensityGrid { resolution, values, majorant })
+    }
+
+    // Evaluates `density` at the centre of every voxel, in unit cube coordinates.
+    pub fn from_fn(resolution: [usize; 3], density: impl Fn(& Point3) -> f64) -> Result<DensityGrid, GridError> {
+        let [nx, ny, nz] = resolution;
+        let mut values = Vec::with_capacity(nx * ny * nz);
+
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let point = Point3::build(
+                        (x as f64 + 0.5) / nx as f64,
+                        (y as f64 + 0.5) / ny as f64,
+                        (z as f64 + 0.5) / nz as f64
+                    );
+                    values.push(density(&point) as f32);
+                }
+            }
+        }
+
+        DensityGrid::from(resolution, values)
+    }
+
+    // A cloud-like blob of fBm noise. Noise below `threshold` is cut away, which
+    // leaves gaps between the puffs, and the density fades out towards the edges
+    // of the cube so the volume doesn't show the shape of its box.
+    pub fn from_noise(resolution: [usize; 3], frequency: f64, octaves: usize, threshold: f64, seed: u64) -> Result<DensityGrid, GridError> {
+        let perlin = Perlin::seed(seed);
+        let centre = Point3::build(0.5, 0.5, 0.5);
+
+        DensityGrid::from_fn(resolution, |point| {
+            let falloff = (1.0 - 2.0 * (point - &centre).length()).max(0.0);
+            let noise = fbm(&perlin, &(point * frequency), octaves, DEFAULT_LACUNARITY, DEFAULT_GAIN);
+            (noise + falloff - threshold).max(0.0) * falloff
+        })
+    }
+
+    // Reads a headerless dense grid, as many volume datasets are distributed,
+    // with the resolution and sample type given separately.
+    pub fn load_raw(path: & Path, resolution: [usize; 3], sample_type: SampleType) -> Result<DensityGrid, GridError> {
+        let bytes = fs::read(path).map_err(|error| GridError::Io(path.to_path_buf(), error))?;
+
+        let expected = resolution[0] * resolution[1] * resolution[2] * sample_type.size();
+        if bytes.len() != expected {
+            return Err(GridError::Size { path: path.to_path_buf(), expected, found: bytes.len() })
+        }
+
+        let values = match sample_type {
+            SampleType::U8 => bytes.iter().map(|&byte| byte as f32 / 255.0).collect(),
+            SampleType::U16 => bytes.chunks_exact(2)
+                .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]) as f32 / 65535.0)
+                .collect(),
+            SampleType::F32 => bytes.chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect()
+        };
+
+        DensityGrid::from(resolution, values)
+    }
+
+    pub fn resolution(& self) -> [usize; 3] {
+        self.resolution
+    }
+
+    pub fn majorant(& self) -> f64 {
+        self.majorant
+    }
+
+    // Trilinear lookup at a point in unit cube coordinates. Outside the
+    // cube the nearest edge values carry on.
+    pub fn density(& self, point: & Point3) -> f64 {
+        let mut base = [0; 3];
+        let mut fraction = [0.0; 3];
+
+        for axis in 0..3 {
+            let n = self.resolution[axis];
+            let position = (point[axis] * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            let floor = position.floor();
+            base[axis] = (floor as usize).min(n.saturating_sub(2));
+            fraction[axis] = position - base[axis] as f64;
+        }
+
+        let mut accumulated = 0.0;
+        for corner in 0..8 {
+            let offset = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let mut weight = 1.0;
+            let mut index = [0; 3];
+
+            for axis in 0..3 {
+                let step = offset[axis].min(self.resolution[axis] - 1);
+                index[axis] = base[axis] + step;
+                weight *= if offset[axis] == 1 { fraction[axis] } else { 1.0 - fraction[axis] };
+            }
+
+            if weight > 0.0 {
+                accumulated += weight * self.value(index) as f64;
+            }
+        }
+
+        accumulated
+    }
+
+    fn value(& self, [x, y, z]: [usize; 3]) -> f32 {
+        self.values[x + self.resolution[0] * (y + self.resolution[1] * z)]
+    }
+}
@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use crate::common::interval::Interval;
+use crate::common::random::{random_f64_standard, Prng};
+use crate::common::ray::Ray;
+use crate::common::vec3::{ Point3, Vector3 };
+
+use crate::materials::MatArc;
+
+use super::aabb::Aabb;
+use super::density_grid::DensityGrid;
+use super::hittable::{ HitRecord, Hittable };
+
+// A volume whose density varies through space, given by a grid stretched over
+// an axis-aligned box, like a cloud or a plume of smoke. Free flights are
+// sampled with delta tracking: the grid's majorant gives tentative collisions
+// at a constant rate, and each one is kept with probability density / majorant,
+// so the regions without much in them cost no more to cross than empty space.
+// Shadow rays see through it, dimmed by its transmittance.
+pub struct GridVolume {
+    grid: Arc<DensityGrid>,
+    bbox: Aabb,
+    minimum: Point3,
+    extent: Vector3,
+    // Multiplies every grid value, so one grid can be reused thicker or thinner.
+    density_scale: f64,
+    phase_function: MatArc
+}
+
+impl GridVolume {
+    pub fn build(grid: & Arc<DensityGrid>, a: & Point3, b: & Point3, density_scale: f64, phase_function: & MatArc) -> GridVolume {
+        GridVolume::from(Arc::clone(grid), Aabb::from_points(a, b), density_scale, Arc::clone(phase_function))
+    }
+
+    pub fn from(grid: Arc<DensityGrid>, bbox: Aabb, density_scale: f64, phase_function: MatArc) -> GridVolume {
+        let minimum = Point3::build(bbox.axis_interval(0).min(), bbox.axis_interval(1).min(), bbox.axis_interval(2).min());
+        let extent = Vector3::build(bbox.axis_interval(0).size(), bbox.axis_interval(1).size(), bbox.axis_interval(2).size());
+
+        GridVolume {
+            grid,
+            bbox,
+            minimum,
+            extent,
+            density_scale,
+            phase_function
+        }
+    }
+
+    fn majorant(& self) -> f64 {
+        self.grid.majorant() * self.density_scale
+    }
+
+    fn density(& self, point: & Point3) -> f64 {
+        let local = Point3::build(
+            (point.x() - self.minimum.x()) / self.extent.x(),
+            (point.y() - self.minimum.y()) / self.extent.y(),
+            (point.z() - self.minimum.z()) / self.extent.z()
+        );
+        self.grid.density(&local) * self.density_scale
+    }
+}
+
+impl Hittable for GridVolume {
+    fn hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        let (inside, majorant) = match (self.bbox.clip(ray, ray_t), self.majorant()) {
+            (Some(inside), majorant) if majorant > 0.0 => (inside, majorant),
+            _ => return (false, None)
+        };
+
+        // Distances are sampled in world units, so convert them to ray parameter steps.
+        let step_scale = 1.0 / (majorant * ray.direction().length());
+        let mut t = inside.min();
+
+        loop {
+            t -= (1.0 - random_f64_standard(rng)).ln() * step_scale;
+            if t >= inside.max() {
+                return (false, None)
+            }
+
+            let point = ray.at(&t);
+            if random_f64_standard(rng) * majorant < self.density(&point) {
+                // Scattering inside a volume has no surface, so the normal and side are arbitrary.
+                let hit_record = HitRecord {
+                    t,
+                    normal: Some(Vector3::build(1.0, 0.0, 0.0)),
+                    material: Arc::clone(&self.phase_function),
+                    point,
+                    u: 0.0,
+                    v: 0.0,
+                    front_face: Some(true),
+                    object_id: 0,
+                    material_id: 0
+                };
+
+                return (true, Some(hit_record))
+            }
+        }
+    }
+
+    fn bounding_box(& self) -> Aabb {
+        self.bbox
+    }
+
+    fn surface_hit(& self, _ray: & Ray, _ray_t: & Interval, _rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        (false, None)
+    }
+
+    // Estimates the fraction of light that gets through the volume along the
+    // ray over ray_t, using ratio tracking. Rather than stopping at the first
+    // real collision like delta tracking, it walks every tentative collision to
+    // the end and multiplies in the chance of each being null, which gives a
+    // smooth estimate instead of a 0 or 1.
+    fn transmittance(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> f64 {
+        let (inside, majorant) = match (self.bbox.clip(ray, ray_t), self.majorant()) {
+            (Some(inside), majorant) if majorant > 0.0 => (inside, majorant),
+            _ => return 1.0
+        };
+
+        let step_scale = 1.0 / (majorant * ray.direction().length());
+        let mut transmittance = 1.0;
+        let mut t = inside.min();
+
+        loop {
+            t -= (1.0 - random_f64_standard(rng)).ln() * step_scale;
+            if t >= inside.max() {
+                return transmittance
+            }
+            transmittance *= 1.0 - self.density(&ray.at(&t)) / majorant;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::materials::isotropic::Isotropic;
+
+    use super::*;
+
+    // Ratio tracking and delta tracking estimate the same transmittance, one
+    // as a fraction and the other as the share of rays that get through.
+    #[test]
+    fn ratio_tracking_agrees_with_delta_tracking() {
+        let grid = Arc::new(DensityGrid::from_fn([16, 16, 16], |point| point.x() * point.y()).expect("grid is valid"));
+        let phase_function: MatArc = Arc::new(Box::new(Isotropic::new()));
+        let volume = GridVolume::build(&grid, &Point3::new(), &Point3::build(2.0, 1.0, 1.0), 2.0, &phase_function);
+
+        let mut rng = Prng::seed(3);
+        let ray = Ray::from(Point3::build(-1.0, 0.7, 0.5), Vector3::build(1.0, 0.0, 0.1));
+        let ray_t = Interval::build(0.0, f64::INFINITY);
+
+        let count = 20000;
+        let ratio: f64 = (0..count).map(|_| volume.transmittance(&ray, &ray_t, &mut rng)).sum::<f64>() / count as f64;
+        let delta = (0..count).filter(|_| !volume.hit(&ray, &ray_t, &mut rng).0).count() as f64 / count as f64;
+
+        let error = (delta * (1.0 - delta) / count as f64).sqrt();
+        assert!(delta > 0.1 && delta < 0.9, "the volume should be partly see-through: {}", delta);
+        assert!((ratio - delta).abs() < 4.0 * error, "ratio tracking gave {}, delta tracking {}", ratio, delta);
+    }
+}
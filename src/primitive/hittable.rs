@@ -1,17 +1,26 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::common::interval::Interval; 
+use crate::common::random::Prng;
 use crate::common::ray::Ray;
 use crate::common::vec3::{ dot_product, Point3, Vector3 };
 
-use crate::materials::Material;
+use crate::materials::MatArc;
+
+use super::aabb::Aabb;
 
 pub struct HitRecord {
     pub point: Point3,
     pub normal: Option<Vector3>,
-    pub material: Rc<Box<dyn Material>>,
+    pub material: MatArc,
     pub t: f64,
-    pub front_face: Option<bool>
+    // Surface coordinates of the hit; what they mean depends on the primitive.
+    pub u: f64,
+    pub v: f64,
+    pub front_face: Option<bool>,
+    // Set by a Tagged object around the primitive, for the ID AOVs; 0 otherwise.
+    pub object_id: usize,
+    pub material_id: usize
 }
 
 impl HitRecord {
@@ -23,8 +32,8 @@ impl HitRecord {
         self.point
     }
 
-    pub fn material<'a>(&self) -> Rc<Box<dyn Material>>{
-        Rc::clone(&self.material)
+    pub fn material<'a>(&self) -> MatArc{
+        Arc::clone(&self.material)
     }
 
     pub fn front_face(&self) -> bool {
@@ -44,6 +53,63 @@ impl HitRecord {
     }
 }
 
-pub trait Hittable {
-    fn hit(& self, ray: & Ray, ray_t: & Interval) -> (bool, Option<HitRecord>);
-}
\ No newline at end of file
+// Scenes are traversed from every render thread at once.
+pub trait Hittable: Send + Sync {
+    // `rng` is the path's own generator, for objects whose hits are random, like
+    // volumes. They sample where they scatter here, so the nearest hit search
+    // through lists, BVHs and transforms handles them like any surface.
+    fn hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>);
+
+    fn bounding_box(& self) -> Aabb;
+
+    // Like hit, but sees through volumes to the nearest surface. Shadow rays use
+    // it, and take the volumes on the way into account with transmittance.
+    fn surface_hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        self.hit(ray, ray_t, rng)
+    }
+
+    // The fraction of light along the ray over ray_t that gets through the
+    // object's volumes. Surfaces block nothing here; surface_hit finds those.
+    fn transmittance(& self, _ray: & Ray, _ray_t: & Interval, _rng: &mut Prng) -> f64 {
+        1.0
+    }
+
+    // For objects that can be sampled as lights: the probability density, per
+    // unit solid angle, of random_direction picking the ray's direction from its
+    // origin. Zero if the ray misses.
+    fn pdf_value(& self, _ray: & Ray, _rng: &mut Prng) -> f64 {
+        0.0
+    }
+
+    // A direction from `origin` towards a random point on the object, as seen at `time`.
+    fn random_direction(& self, _origin: & Point3, _time: f64, _rng: &mut Prng) -> Vector3 {
+        Vector3::build(1.0, 0.0, 0.0)
+    }
+}
+// One object in more than one place, like a light that is both part of the
+// world and one of the lights sampled directly.
+impl<T: Hittable + ?Sized> Hittable for Arc<T> {
+    fn hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        (**self).hit(ray, ray_t, rng)
+    }
+
+    fn bounding_box(& self) -> Aabb {
+        (**self).bounding_box()
+    }
+
+    fn surface_hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        (**self).surface_hit(ray, ray_t, rng)
+    }
+
+    fn transmittance(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> f64 {
+        (**self).transmittance(ray, ray_t, rng)
+    }
+
+    fn pdf_value(& self, ray: & Ray, rng: &mut Prng) -> f64 {
+        (**self).pdf_value(ray, rng)
+    }
+
+    fn random_direction(& self, origin: & Point3, time: f64, rng: &mut Prng) -> Vector3 {
+        (**self).random_direction(origin, time, rng)
+    }
+}
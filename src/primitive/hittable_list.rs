@@ -1,47 +1,68 @@
-use std::cell::RefCell;
 use std::vec;
 
 use crate::common::interval::Interval;
+use crate::common::random::{ random_f64_standard, Prng };
+use crate::common::ray::Ray;
+use crate::common::vec3::{ Point3, Vector3 };
 
+use super::aabb::Aabb;
 use super::hittable::{ HitRecord, Hittable };
 
 pub struct HittableList<'a> {
-    objects: RefCell<Vec<Box<dyn Hittable + 'a>>>
+    objects: Vec<Box<dyn Hittable + 'a>>
 }
 
 impl HittableList<'_> {
     pub fn clear(&mut self) {
-        self.objects.get_mut().clear();
+        self.objects.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
     }
 }
 
 impl<'a> HittableList<'a> {
     pub fn new() -> HittableList<'a> {
         HittableList {
-            objects: RefCell::new(Vec::new())
+            objects: Vec::new()
         }
     }
 
     pub fn build(object: impl Hittable + 'a) -> HittableList<'a> {
-        HittableList { objects: RefCell::new(vec![Box::new(object)]) }
+        HittableList { objects: vec![Box::new(object)] }
+    }
+
+    pub fn add(&mut self, object: impl Hittable + 'a) {
+        self.objects.push(Box::new(object));
     }
 
-    pub fn add(&self, object: impl Hittable + 'a) {
-        self.objects.try_borrow_mut().expect("help").push(Box::new(object));
+    // For objects whose type is only known at runtime.
+    pub fn add_boxed(&mut self, object: Box<dyn Hittable + 'a>) {
+        self.objects.push(object);
+    }
+
+    // Hands the objects over, e.g. to build a BvhNode out of them.
+    pub fn into_objects(self) -> Vec<Box<dyn Hittable + 'a>> {
+        self.objects
     }
 }   
 
-impl Hittable for HittableList<'_> {
-    fn hit(& self, ray: & crate::common::ray::Ray, ray_t: & Interval) -> (bool, Option<super::hittable::HitRecord>) {
+impl HittableList<'_> {
+    // The nearest hit on any object, or with `surfaces_only` the nearest surface.
+    fn closest_hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng, surfaces_only: bool) -> (bool, Option<HitRecord>) {
         let mut hit_rec: Option<HitRecord> = None;
         let mut hit_anything = false;
         let mut closest_t = ray_t.max();
 
-        let objects = self.objects.try_borrow_mut().expect("help");
-
-        for object in objects.iter() {
+        for object in self.objects.iter() {
             let ptr = object;
-            let (hit, temp_rec) = ptr.hit(ray, &Interval::build(ray_t.min(), closest_t));
+            let interval = Interval::build(ray_t.min(), closest_t);
+            let (hit, temp_rec) = if surfaces_only {
+                ptr.surface_hit(ray, &interval, rng)
+            } else {
+                ptr.hit(ray, &interval, rng)
+            };
             if hit { 
                 let rec_val = temp_rec.expect("HittableList.hit(): hit registered, but no record available");
                 closest_t = (&rec_val).t.clone();
@@ -52,4 +73,44 @@ impl Hittable for HittableList<'_> {
 
         (hit_anything, hit_rec)
     }
-}
\ No newline at end of file
+}
+
+impl Hittable for HittableList<'_> {
+    fn hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        self.closest_hit(ray, ray_t, rng, false)
+    }
+
+    fn bounding_box(& self) -> Aabb {
+        self.objects.iter()
+            .fold(Aabb::new(), |bbox, object| Aabb::enclosing(&bbox, &object.bounding_box()))
+    }
+
+    fn surface_hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        self.closest_hit(ray, ray_t, rng, true)
+    }
+
+    fn transmittance(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> f64 {
+        self.objects.iter().map(|object| object.transmittance(ray, ray_t, rng)).product()
+    }
+
+    // As lights, the objects are picked between evenly.
+    fn pdf_value(& self, ray: & Ray, rng: &mut Prng) -> f64 {
+        if self.objects.is_empty() {
+            return 0.0
+        }
+
+        let total: f64 = self.objects.iter().map(|object| object.pdf_value(ray, rng)).sum();
+        total / self.objects.len() as f64
+    }
+
+    fn random_direction(& self, origin: & Point3, time: f64, rng: &mut Prng) -> Vector3 {
+        let index = ((random_f64_standard(rng) * self.objects.len() as f64) as usize).min(self.objects.len() - 1);
+        self.objects[index].random_direction(origin, time, rng)
+    }
+}
+
+impl Default for HittableList<'_> {
+    fn default() -> Self {
+        HittableList::new()
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{ Path, PathBuf };
+use std::sync::Arc;
+
+use crate::common::colour::Colour;
+use crate::common::vec3::{ Point3, Vector3 };
+
+use crate::materials::dielectric::Dialectric;
+use crate::materials::diffuse_light::DiffuseLight;
+use crate::materials::lambertian::Lambertian;
+use crate::materials::metal::Metal;
+use crate::materials::MatArc;
+
+use crate::textures::image_texture::{ ImageTexture, TextureError, WrapMode };
+use crate::textures::TexArc;
+
+use super::triangle::Triangle;
+
+// Used when a transparent MTL material gives no optical density (Ni).
+const DEFAULT_REFRACTIVE_INDEX: f64 = 1.5;
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(PathBuf, io::Error),
+    Parse { path: PathBuf, line: usize, message: String },
+    Texture(TextureError),
+    NoFaces(PathBuf)
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            ObjError::Parse { path, line, message } => write!(f, "{}:{}: {}", path.display(), line, message),
+            ObjError::Texture(error) => write!(f, "{}", error),
+            ObjError::NoFaces(path) => write!(f, "{}: no faces found", path.display())
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+// Reads the triangles of a Wavefront OBJ file. Polygons are split into fans,
+// and faces take their material from the last `usemtl` before them, falling
+// back to the given material if there is none or it isn't in any `mtllib`. A
+// `mtllib` that can't be read only leaves its materials out, and its error is
+// returned beside the triangles for the caller to report.
+pub fn load_triangles(path: & Path, default_material: & MatArc) -> Result<(Vec<Triangle>, Vec<ObjError>), ObjError> {
+    let source = fs::read_to_string(path).map_err(|error| ObjError::Io(path.to_path_buf(), error))?;
+    let parse_error = |line: usize, message: String| ObjError::Parse { path: path.to_path_buf(), line, message };
+
+    let mut positions: Vec<Point3> = Vec::new();
+    let mut normals: Vec<Vector3> = Vec::new();
+    let mut uvs: Vec<(f64, f64)> = Vec::new();
+    let mut materials: HashMap<String, MatArc> = HashMap::new();
+    let mut current_material = Arc::clone(default_material);
+    let mut triangles = Vec::new();
+    let mut warnings = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) if !keyword.starts_with('#') => keyword,
+            _ => continue
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                let [x, y, z] = parse_floats(&arguments).map_err(|message| parse_error(line_number, message))?;
+                positions.push(Point3::build(x, y, z));
+            }
+            "vn" => {
+                let [x, y, z] = parse_floats(&arguments).map_err(|message| parse_error(line_number, message))?;
+                normals.push(Vector3::build(x, y, z));
+            }
+            "vt" => {
+                // v is optional and defaults to 0, and the optional w coordinate is ignored.
+                let [u] = parse_floats(&arguments).map_err(|message| parse_error(line_number, message))?;
+                let v = match arguments.get(1) {
+                    Some(v) => v.parse().map_err(|_| parse_error(line_number, format!("invalid number '{}'", v)))?,
+                    None => 0.0
+                };
+                uvs.push((u, v));
+            }
+            "f" => {
+                if arguments.len() < 3 {
+                    return Err(parse_error(line_number, "face needs at least three vertices".to_string()));
+                }
+
+                let mut corners = Vec::with_capacity(arguments.len());
+                for argument in &arguments {
+                    let corner = parse_face_vertex(argument, positions.len(), uvs.len(), normals.len())
+                        .map_err(|message| parse_error(line_number, message))?;
+                    corners.push(corner);
+                }
+
+                for i in 1..corners.len() - 1 {
+                    let fan = [corners[0], corners[i], corners[i + 1]];
+                    triangles.push(build_triangle(&fan, &positions, &uvs, &normals, &current_material));
+                }
+            }
+            "mtllib" => {
+                let directory = path.parent().unwrap_or(Path::new(""));
+                for library in &arguments {
+                    match load_materials(&directory.join(library)) {
+                        Ok(library) => materials.extend(library),
+                        Err(error @ ObjError::Io(..)) => warnings.push(error),
+                        Err(error) => return Err(error)
+                    }
+                }
+            }
+            "usemtl" => {
+                let name = arguments.join(" ");
+                current_material = match materials.get(&name) {
+                    Some(material) => Arc::clone(material),
+                    None => Arc::clone(default_material)
+                };
+            }
+            // Groups, objects, smoothing groups and anything else don't affect the geometry.
+            _ => {}
+        }
+    }
+
+    Ok((triangles, warnings))
+}
+
+#[derive(Clone, Copy)]
+struct FaceVertex {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>
+}
+
+// Parses one `v`, `v/vt`, `v//vn` or `v/vt/vn` face entry into zero-based indices.
+fn parse_face_vertex(entry: &str, position_count: usize, uv_count: usize, normal_count: usize) -> Result<FaceVertex, String> {
+    let mut parts = entry.split('/');
+
+    let position = match parts.next() {
+        Some(part) => resolve_index(part, position_count)?,
+        None => return Err(format!("empty face vertex '{}'", entry))
+    };
+    let uv = match parts.next() {
+        Some("") | None => None,
+        Some(part) => Some(resolve_index(part, uv_count)?)
+    };
+    let normal = match parts.next() {
+        Some("") | None => None,
+        Some(part) => Some(resolve_index(part, normal_count)?)
+    };
+
+    Ok(FaceVertex { position, uv, normal })
+}
+
+// OBJ indices start at 1, and negative ones count back from the latest element.
+fn resolve_index(part: &str, count: usize) -> Result<usize, String> {
+    let index: i64 = part.parse().map_err(|_| format!("invalid index '{}'", part))?;
+    let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!("index {} out of range, only {} defined so far", index, count));
+    }
+
+    Ok(resolved as usize)
+}
+
+fn build_triangle(
+    corners: & [FaceVertex; 3],
+    positions: & [Point3],
+    uvs: & [(f64, f64)],
+    normals: & [Vector3],
+    material: & MatArc
+) -> Triangle {
+    let [a, b, c] = corners.map(|corner| positions[corner.position]);
+    let mut triangle = Triangle::build(&a, &b, &c, material);
+
+    if let [Some(a), Some(b), Some(c)] = corners.map(|corner| corner.normal) {
+        triangle = triangle.with_vertex_normals([normals[a], normals[b], normals[c]]);
+    }
+    if let [Some(a), Some(b), Some(c)] = corners.map(|corner| corner.uv) {
+        triangle = triangle.with_vertex_uvs([uvs[a], uvs[b], uvs[c]]);
+    }
+
+    triangle
+}
+
+fn parse_floats<const N: usize>(arguments: & [&str]) -> Result<[f64; N], String> {
+    if arguments.len() < N {
+        return Err(format!("expected {} numbers, found {}", N, arguments.len()));
+    }
+
+    let mut values = [0.0; N];
+    for (value, argument) in values.iter_mut().zip(arguments) {
+        *value = argument.parse().map_err(|_| format!("invalid number '{}'", argument))?;
+    }
+
+    Ok(values)
+}
+
+
+// MTL materials.
+
+// The subset of an MTL material that maps onto our materials.
+struct MtlMaterial {
+    diffuse: Colour,
+    diffuse_map: Option<TexArc>,
+    specular: Colour,
+    emissive: Colour,
+    transmission: Colour,
+    shininess: f64,
+    refractive_index: Option<f64>,
+    dissolve: f64,
+    illumination: i32
+}
+
+impl MtlMaterial {
+    fn new() -> MtlMaterial {
+        MtlMaterial {
+            diffuse: Colour::build(0.8, 0.8, 0.8),
+            diffuse_map: None,
+            specular: Colour::new(),
+            emissive: Colour::new(),
+            transmission: Colour::build(1.0, 1.0, 1.0),
+            shininess: 0.0,
+            refractive_index: None,
+            dissolve: 1.0,
+            illumination: 2
+        }
+    }
+
+    // Emitters become lights, see-through or refracting materials become
+    // glass, mirror-like or mostly specular ones metal, and the rest diffuse.
+    fn to_material(&self) -> MatArc {
+        let max_component = |colour: & Colour| colour.x().max(colour.y()).max(colour.z());
+
+        if max_component(&self.emissive) > 0.0 {
+            return Arc::new(Box::new(DiffuseLight::from(self.emissive)))
+        }
+
+        let transparent = self.dissolve < 1.0 || matches!(self.illumination, 4 | 6 | 7 | 9);
+        if transparent {
+            let refractive_index = self.refractive_index.unwrap_or(DEFAULT_REFRACTIVE_INDEX);
+            return Arc::new(Box::new(Dialectric::from(self.transmission, refractive_index)))
+        }
+
+        let specular = max_component(&self.specular);
+        if specular > 0.0 && (self.illumination == 3 || specular > max_component(&self.diffuse)) {
+            // Phong exponent to a roughness-like fuzz: 0 stays fully rough, high exponents approach a mirror.
+            let fuzz = f64::sqrt(2.0 / (self.shininess + 2.0)).clamp(0.0, 1.0);
+            return Arc::new(Box::new(Metal::from(self.specular, fuzz)))
+        }
+
+        match &self.diffuse_map {
+            Some(texture) => Arc::new(Box::new(Lambertian::from_texture(Arc::clone(texture)))),
+            None => Arc::new(Box::new(Lambertian::from(self.diffuse)))
+        }
+    }
+}
+
+fn load_materials(path: & Path) -> Result<HashMap<String, MatArc>, ObjError> {
+    let source = fs::read_to_string(path).map_err(|error| ObjError::Io(path.to_path_buf(), error))?;
+    let parse_error = |line: usize, message: String| ObjError::Parse { path: path.to_path_buf(), line, message };
+
+    let mut parsed: Vec<(String, MtlMaterial)> = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) if !keyword.starts_with('#') => keyword,
+            _ => continue
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            parsed.push((arguments.join(" "), MtlMaterial::new()));
+            continue;
+        }
+
+        let material = match parsed.last_mut() {
+            Some((_, material)) => material,
+            None => return Err(parse_error(line_number, format!("'{}' before any newmtl", keyword)))
+        };
+
+        let colour = |arguments: & [&str]| {
+            parse_floats(arguments).map(|[r, g, b]| Colour::build(r, g, b))
+                .map_err(|message| parse_error(line_number, message))
+        };
+        let scalar = |arguments: & [&str]| {
+            parse_floats(arguments).map(|[value]| value)
+                .map_err(|message| parse_error(line_number, message))
+        };
+
+        match keyword {
+            "Kd" => material.diffuse = colour(&arguments)?,
+            "Ks" => material.specular = colour(&arguments)?,
+            "Ke" => material.emissive = colour(&arguments)?,
+            "Tf" => material.transmission = colour(&arguments)?,
+            "Ns" => material.shininess = scalar(&arguments)?,
+            "Ni" => material.refractive_index = Some(scalar(&arguments)?),
+            "d" => material.dissolve = scalar(&arguments)?,
+            "Tr" => material.dissolve = 1.0 - scalar(&arguments)?,
+            "illum" => material.illumination = scalar(&arguments)? as i32,
+            "map_Kd" => {
+                // Options like -s or -o come before the file name, which is the last argument.
+                let file_name = match arguments.last() {
+                    Some(file_name) => file_name,
+                    None => return Err(parse_error(line_number, "map_Kd without a file name".to_string()))
+                };
+                let texture_path = path.parent().unwrap_or(Path::new("")).join(file_name);
+                let texture = ImageTexture::load(&texture_path, WrapMode::Repeat).map_err(ObjError::Texture)?;
+                material.diffuse_map = Some(Arc::new(Box::new(texture)));
+            }
+            // Ambient colour, the other texture maps and the rest have no equivalent yet.
+            _ => {}
+        }
+    }
+
+    Ok(parsed.into_iter().map(|(name, material)| (name, material.to_material())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    // Writes `source` to a fresh OBJ file and loads it.
+    fn load(name: &str, source: &str) -> Result<(Vec<Triangle>, Vec<ObjError>), ObjError> {
+        let directory = env::temp_dir().join(format!("rtow_rust_obj_{}_{}", process::id(), name));
+        fs::create_dir_all(&directory).expect("temporary directory is writable");
+        let path = directory.join("mesh.obj");
+        fs::write(&path, source).expect("temporary file is writable");
+
+        let default_material: MatArc = Arc::new(Box::new(Lambertian::new()));
+        let result = load_triangles(&path, &default_material);
+        fs::remove_dir_all(&directory).ok();
+        result
+    }
+
+    #[test]
+    fn texture_coordinates_take_one_to_three_components() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.5\nvt 0.25 0.75\nvt 0.1 0.2 0.3\nf 1/1 2/2 3/3\n";
+        let (triangles, _) = load("vt", source).expect("OBJ is valid");
+        assert_eq!(triangles.len(), 1);
+
+        assert!(load("vt_empty", "vt\n").is_err());
+    }
+
+    #[test]
+    fn missing_material_library_falls_back_to_the_default() {
+        let source = "mtllib missing.mtl\nusemtl red\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+        let (triangles, warnings) = load("mtllib", source).expect("a missing mtllib isn't an error");
+        assert_eq!(triangles.len(), 1);
+        assert!(matches!(warnings.as_slice(), [ObjError::Io(path, _)] if path.ends_with("missing.mtl")));
+    }
+}
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use crate::common::interval::Interval;
+use crate::common::random::{ random_f64_standard, Prng };
+use crate::common::ray::Ray;
+use crate::common::vec3::{ cross_product, dot_product, unit_vector, Point3, Vector3 };
+use crate::common::RAY_MINIMUM_DISTANCE_BEFORE_HIT;
+
+use crate::materials::MatArc;
+
+use super::aabb::Aabb;
+use super::hittable::{ HitRecord, Hittable };
+use super::hittable_list::HittableList;
+
+// Rays closer than this to parallel with the plane are treated as misses.
+const PARALLEL_CUTOFF: f64 = 1e-8;
+
+// A parallelogram spanned by the edges u and v from one corner.
+pub struct Quad {
+    corner: Point3,
+    u: Vector3,
+    v: Vector3,
+    material: MatArc,
+
+    // Plane of the quad, as normal . p = d.
+    normal: Vector3,
+    d: f64,
+    // Maps a point in the plane onto the (u, v) edge coordinates.
+    w: Vector3,
+    area: f64
+}
+
+impl Quad {
+    pub fn build(corner: & Point3, u: & Vector3, v: & Vector3, material: & MatArc) -> Quad {
+        Quad::from(*corner, *u, *v, Arc::clone(material))
+    }
+
+    pub fn from(corner: Point3, u: Vector3, v: Vector3, material: MatArc) -> Quad {
+        let n = cross_product(&u, &v);
+        let normal = unit_vector(&n);
+
+        Quad {
+            corner,
+            u,
+            v,
+            material,
+            normal,
+            d: dot_product(&normal, &corner),
+            w: n / n.length_squared(),
+            area: n.length()
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(& self, ray: & Ray, ray_t: & Interval, _rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        let denominator = dot_product(&self.normal, ray.direction());
+        if denominator.abs() < PARALLEL_CUTOFF {
+            return (false, None)
+        }
+
+        let t = (self.d - dot_product(&self.normal, ray.origin())) / denominator;
+        if !ray_t.surrounds(t) {
+            return (false, None)
+        }
+
+        let p = ray.at(&t);
+        let planar_hit_vector = p - self.corner;
+        let alpha = dot_product(&self.w, &cross_product(&planar_hit_vector, &self.v));
+        let beta = dot_product(&self.w, &cross_product(&self.u, &planar_hit_vector));
+
+        let unit_interval = Interval::build(0.0, 1.0);
+        if !unit_interval.contains(alpha) || !unit_interval.contains(beta) {
+            return (false, None)
+        }
+
+        let mut hit_record = HitRecord {
+            t,
+            normal: None,
+            material: Arc::clone(&self.material),
+            point: p,
+            u: alpha,
+            v: beta,
+            front_face: None,
+            object_id: 0,
+            material_id: 0
+        };
+
+        hit_record.set_face_normal(ray, &self.normal);
+
+        (true, Some(hit_record))
+    }
+
+    fn bounding_box(& self) -> Aabb {
+        let diagonal_box = Aabb::from_points(&self.corner, &(self.corner + self.u + self.v));
+        let other_diagonal_box = Aabb::from_points(&(self.corner + self.u), &(self.corner + self.v));
+        Aabb::enclosing(&diagonal_box, &other_diagonal_box)
+    }
+
+    // Points are picked uniformly over the area, so the density per solid angle
+    // is distance^2 / (cosine * area).
+    fn pdf_value(& self, ray: & Ray, rng: &mut Prng) -> f64 {
+        let hit_record = match self.hit(ray, &Interval::build(RAY_MINIMUM_DISTANCE_BEFORE_HIT, f64::INFINITY), rng) {
+            (true, Some(hit_record)) => hit_record,
+            _ => return 0.0
+        };
+
+        let length = ray.direction().length();
+        let distance_squared = hit_record.t * hit_record.t * ray.direction().length_squared();
+        let cosine = (dot_product(ray.direction(), &self.normal) / length).abs();
+
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random_direction(& self, origin: & Point3, _time: f64, rng: &mut Prng) -> Vector3 {
+        let point = self.corner + self.u * random_f64_standard(rng) + self.v * random_f64_standard(rng);
+        point - *origin
+    }
+}
+
+// The six faces of the axis-aligned box with opposite corners a and b,
+// with normals facing outwards.
+pub fn make_box(a: & Point3, b: & Point3, material: & MatArc) -> HittableList<'static> {
+    let mut sides = HittableList::new();
+
+    let min = Point3::build(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()));
+    let max = Point3::build(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z()));
+
+    let dx = Vector3::build(max.x() - min.x(), 0.0, 0.0);
+    let dy = Vector3::build(0.0, max.y() - min.y(), 0.0);
+    let dz = Vector3::build(0.0, 0.0, max.z() - min.z());
+
+    sides.add(Quad::build(&Point3::build(min.x(), min.y(), max.z()), &dx, &dy, material));  // front
+    sides.add(Quad::build(&Point3::build(max.x(), min.y(), max.z()), &-dz, &dy, material)); // right
+    sides.add(Quad::build(&Point3::build(max.x(), min.y(), min.z()), &-dx, &dy, material)); // back
+    sides.add(Quad::build(&Point3::build(min.x(), min.y(), min.z()), &dz, &dy, material));  // left
+    sides.add(Quad::build(&Point3::build(min.x(), max.y(), max.z()), &dx, &-dz, material)); // top
+    sides.add(Quad::build(&Point3::build(min.x(), min.y(), min.z()), &dx, &dz, material));  // bottom
+
+    sides
+}
@@ -1,49 +1,81 @@
 
-use std::rc::Rc;
+use std::f64::consts::PI;
+use std::sync::Arc;
 
 use crate::common::interval::Interval; 
+use crate::common::mat3::Matrix3;
+use crate::common::random::{ random_f64_standard, Prng };
 use crate::common::ray::Ray;
-use crate::common::vec3::{ dot_product, Point3 };
+use crate::common::vec3::{ dot_product, random_unit_vector, unit_vector, Point3, Vector3 };
+use crate::common::RAY_MINIMUM_DISTANCE_BEFORE_HIT;
 
-use crate::materials::Material;
+use crate::materials::MatArc;
 
+use super::aabb::Aabb;
 use super::hittable::{Hittable, HitRecord};
 
 pub struct Sphere {
     center: Point3,
-    material: Rc<Box<dyn Material>>,
+    // How far the center moves between times 0 and 1.
+    motion: Vector3,
+    material: MatArc,
     radius: f64
 }
 
 impl Sphere {
-    pub fn new(material: & Rc<Box<dyn Material>>) -> Sphere {
+    pub fn new(material: & MatArc) -> Sphere {
         Sphere {
             center: Point3::new(),
-            material: Rc::clone(material),
+            motion: Vector3::new(),
+            material: Arc::clone(material),
             radius: 1.0,
         }
     }
 
-    pub fn build(center: & Point3, radius: & f64, material: & Rc<Box<dyn Material>>) -> Sphere{
+    pub fn build(center: & Point3, radius: & f64, material: & MatArc) -> Sphere{
         Sphere {
             center: Point3::clone(center),
-            material: Rc::clone(material),
+            motion: Vector3::new(),
+            material: Arc::clone(material),
             radius: f64::clone(radius)
         }
     }
     
-    pub fn build_explicit(center: (f64, f64, f64), radius: f64, material: & Rc<Box<dyn Material>>) -> Sphere{
+    pub fn build_explicit(center: (f64, f64, f64), radius: f64, material: & MatArc) -> Sphere{
         Sphere {
             center: Point3::build(center.0, center.1, center.2),
-            material: Rc::clone(material),
+            motion: Vector3::new(),
+            material: Arc::clone(material),
             radius: radius
         }
     }
+
+    // Moves the sphere in a straight line, from its center at time 0 to
+    // `end_center` at time 1.
+    pub fn with_motion(mut self, end_center: & Point3) -> Sphere {
+        self.motion = end_center - &self.center;
+        self
+    }
+
+    fn center_at(& self, time: f64) -> Point3 {
+        self.center + self.motion * time
+    }
+}
+
+// Longitude and latitude of a point on the unit sphere, both scaled to [0, 1]:
+// u goes around the y axis starting from -x, v from the south pole (y = -1) to the north.
+fn sphere_uv(point: & Point3) -> (f64, f64) {
+    let theta = f64::acos(-point.y());
+    let phi = f64::atan2(-point.z(), point.x()) + PI;
+
+    (phi / (2.0 * PI), theta / PI)
 }
 
 impl Hittable for Sphere {
-    fn hit(& self, ray: & Ray, ray_t: & Interval) -> (bool, Option<HitRecord>) {
-        let origin_to_center = &self.center - ray.origin();
+    fn hit(& self, ray: & Ray, ray_t: & Interval, _rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        // Held still outside times 0 to 1, so the bounding box stays true.
+        let center = self.center_at(ray.time().clamp(0.0, 1.0));
+        let origin_to_center = &center - ray.origin();
         let a = ray.direction().length_squared();
         let h = dot_product(ray.direction(), &origin_to_center);
         let c = origin_to_center.length_squared() - self.radius * self.radius;
@@ -63,14 +95,19 @@ impl Hittable for Sphere {
             } 
             
         let p = ray.at(&root);
-        let outward_normal = (&p - &self.center) / self.radius;
+        let outward_normal = (&p - &center) / self.radius;
+        let (u, v) = sphere_uv(&outward_normal);
         
         let mut hit_record = HitRecord{
             t: root,
             normal: None, 
-            material: Rc::clone(&self.material),
+            material: Arc::clone(&self.material),
             point: p,
-            front_face: None
+            u,
+            v,
+            front_face: None,
+            object_id: 0,
+            material_id: 0
         };
 
         hit_record.set_face_normal(&ray, &outward_normal);
@@ -78,4 +115,47 @@ impl Hittable for Sphere {
         (true, Some(hit_record))
         
     }
+
+    fn bounding_box(& self) -> Aabb {
+        // Encloses the sphere at both ends of its motion, and so everywhere between.
+        let radius_vector = Vector3::build(self.radius, self.radius, self.radius);
+        let start = Aabb::from_points(&(self.center - radius_vector), &(self.center + radius_vector));
+        let end_center = self.center_at(1.0);
+        let end = Aabb::from_points(&(end_center - radius_vector), &(end_center + radius_vector));
+        Aabb::enclosing(&start, &end)
+    }
+
+    // Directions are picked uniformly over the cone the sphere fills as seen
+    // from the origin, or over all directions from inside it.
+    fn pdf_value(& self, ray: & Ray, rng: &mut Prng) -> f64 {
+        match self.hit(ray, &Interval::build(RAY_MINIMUM_DISTANCE_BEFORE_HIT, f64::INFINITY), rng) {
+            (true, Some(_)) => {}
+            _ => return 0.0
+        }
+
+        let center = self.center_at(ray.time().clamp(0.0, 1.0));
+        let distance_squared = (center - *ray.origin()).length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return 1.0 / (4.0 * PI)
+        }
+
+        let cos_theta_max = f64::sqrt(1.0 - self.radius * self.radius / distance_squared);
+        1.0 / (2.0 * PI * (1.0 - cos_theta_max))
+    }
+
+    fn random_direction(& self, origin: & Point3, time: f64, rng: &mut Prng) -> Vector3 {
+        let direction = self.center_at(time.clamp(0.0, 1.0)) - *origin;
+        let distance_squared = direction.length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return random_unit_vector(rng)
+        }
+
+        let cos_theta_max = f64::sqrt(1.0 - self.radius * self.radius / distance_squared);
+        let cos_theta = 1.0 + random_f64_standard(rng) * (cos_theta_max - 1.0);
+        let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
+        let phi = 2.0 * PI * random_f64_standard(rng);
+
+        let local = Vector3::build(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta);
+        Matrix3::orthonormal_basis(&unit_vector(&direction)).to_world(&local)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,69 @@
+use crate::common::interval::Interval;
+use crate::common::random::Prng;
+use crate::common::ray::Ray;
+use crate::common::vec3::{ Point3, Vector3 };
+
+use super::aabb::Aabb;
+use super::hittable::{ HitRecord, Hittable };
+
+// Marks every hit on an object with the object's ID and its material's, for
+// the ID AOVs that compositors pick mattes out of. Otherwise it passes
+// everything straight through.
+pub struct Tagged<'a> {
+    object: Box<dyn Hittable + 'a>,
+    object_id: usize,
+    material_id: usize
+}
+
+impl<'a> Tagged<'a> {
+    pub fn build(object: impl Hittable + 'a, object_id: usize, material_id: usize) -> Tagged<'a> {
+        Tagged::from(Box::new(object), object_id, material_id)
+    }
+
+    pub fn from(object: Box<dyn Hittable + 'a>, object_id: usize, material_id: usize) -> Tagged<'a> {
+        Tagged {
+            object,
+            object_id,
+            material_id
+        }
+    }
+}
+
+impl Tagged<'_> {
+    fn tag(& self, hit: (bool, Option<HitRecord>)) -> (bool, Option<HitRecord>) {
+        match hit {
+            (true, Some(mut hit_record)) => {
+                hit_record.object_id = self.object_id;
+                hit_record.material_id = self.material_id;
+                (true, Some(hit_record))
+            }
+            _ => (false, None)
+        }
+    }
+}
+
+impl Hittable for Tagged<'_> {
+    fn hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        self.tag(self.object.hit(ray, ray_t, rng))
+    }
+
+    fn bounding_box(& self) -> Aabb {
+        self.object.bounding_box()
+    }
+
+    fn surface_hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        self.tag(self.object.surface_hit(ray, ray_t, rng))
+    }
+
+    fn transmittance(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> f64 {
+        self.object.transmittance(ray, ray_t, rng)
+    }
+
+    fn pdf_value(& self, ray: & Ray, rng: &mut Prng) -> f64 {
+        self.object.pdf_value(ray, rng)
+    }
+
+    fn random_direction(& self, origin: & Point3, time: f64, rng: &mut Prng) -> Vector3 {
+        self.object.random_direction(origin, time, rng)
+    }
+}
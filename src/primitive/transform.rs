@@ -0,0 +1,449 @@
+use std::sync::Arc;
+
+use crate::common::interval::Interval;
+use crate::common::mat4::Matrix4;
+use crate::common::quaternion::{ slerp, Quaternion };
+use crate::common::random::Prng;
+use crate::common::ray::Ray;
+use crate::common::vec3::{ unit_vector, Point3, Vector3 };
+
+use super::aabb::Aabb;
+use super::hittable::{ HitRecord, Hittable };
+
+// Times at which an AnimatedTransform is sampled to bound its path.
+const MOTION_BOUND_STEPS: usize = 32;
+
+// Places an object in the world with an affine transform. Rays are taken into
+// the object's own space to be intersected, and the hit is brought back out.
+// The object is shared, so one sphere or mesh can be instanced many times.
+pub struct Transform<'a> {
+    object: Arc<dyn Hittable + 'a>,
+    object_to_world: Matrix4,
+    world_to_object: Matrix4,
+    // The inverse transpose, which keeps normals perpendicular to the surface
+    // under non-uniform scaling.
+    normal_to_world: Matrix4,
+    bbox: Aabb
+}
+
+impl<'a> Transform<'a> {
+    pub fn build(object: & Arc<dyn Hittable + 'a>, object_to_world: & Matrix4) -> Transform<'a> {
+        Transform::from(Arc::clone(object), *object_to_world)
+    }
+
+    pub fn from(object: Arc<dyn Hittable + 'a>, object_to_world: Matrix4) -> Transform<'a> {
+        let world_to_object = object_to_world.affine_inverse()
+            .expect("Transform: object_to_world must be invertible.");
+        let bbox = transform_box(&object.bounding_box(), &object_to_world);
+
+        Transform {
+            object,
+            object_to_world,
+            world_to_object,
+            normal_to_world: world_to_object.transpose(),
+            bbox
+        }
+    }
+}
+
+// A transform split into parts that can be interpolated: the object is
+// scaled, then rotated, then translated.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub struct Placement {
+    scale: Vector3,
+    rotation: Quaternion,
+    translation: Vector3
+}
+
+impl Placement {
+    pub fn new() -> Placement {
+        Placement {
+            scale: Vector3::build(1.0, 1.0, 1.0),
+            rotation: Quaternion::identity(),
+            translation: Vector3::new()
+        }
+    }
+
+    pub fn build(scale: & Vector3, rotation: & Quaternion, translation: & Vector3) -> Placement {
+        Placement {
+            scale: *scale,
+            rotation: rotation.normalised(),
+            translation: *translation
+        }
+    }
+
+    pub fn matrix(& self) -> Matrix4 {
+        Matrix4::translation(&self.translation) * Matrix4::from_quaternion(&self.rotation) * Matrix4::scaling(&self.scale)
+    }
+
+    // Scale and translation move linearly, and the rotation turns at a constant rate.
+    pub fn interpolate(start: & Placement, end: & Placement, t: f64) -> Placement {
+        Placement {
+            scale: start.scale * (1.0 - t) + end.scale * t,
+            rotation: slerp(&start.rotation, &end.rotation, t),
+            translation: start.translation * (1.0 - t) + end.translation * t
+        }
+    }
+}
+
+impl Default for Placement {
+    fn default() -> Placement {
+        Placement::new()
+    }
+}
+
+// Like Transform, but moving the object from one placement at time 0 to
+// another at time 1, for motion blur.
+pub struct AnimatedTransform<'a> {
+    object: Arc<dyn Hittable + 'a>,
+    start: Placement,
+    end: Placement,
+    bbox: Aabb
+}
+
+impl<'a> AnimatedTransform<'a> {
+    pub fn build(object: & Arc<dyn Hittable + 'a>, start: & Placement, end: & Placement) -> AnimatedTransform<'a> {
+        AnimatedTransform::from(Arc::clone(object), *start, *end)
+    }
+
+    pub fn from(object: Arc<dyn Hittable + 'a>, start: Placement, end: Placement) -> AnimatedTransform<'a> {
+        let bbox = motion_box(&object.bounding_box(), &start, &end);
+
+        AnimatedTransform {
+            object,
+            start,
+            end,
+            bbox
+        }
+    }
+}
+
+// The box around all eight transformed corners of `bbox`.
+fn transform_box(bbox: & Aabb, matrix: & Matrix4) -> Aabb {
+    let mut result = Aabb::new();
+
+    for corner in box_corners(bbox) {
+        let point = matrix.transform_point(&corner);
+        result = Aabb::enclosing(&result, &Aabb::from_points(&point, &point));
+    }
+
+    result
+}
+
+fn box_corners(bbox: & Aabb) -> [Point3; 8] {
+    let mut corners = [Point3::new(); 8];
+
+    for (index, corner) in corners.iter_mut().enumerate() {
+        let pick = |axis: usize| {
+            let interval = bbox.axis_interval(axis);
+            if index >> axis & 1 == 1 { interval.max() } else { interval.min() }
+        };
+        *corner = Point3::build(pick(0), pick(1), pick(2));
+    }
+
+    corners
+}
+
+// Encloses the box at evenly spaced times, then pads it, since corners swing
+// round on arcs that bulge out between the samples. With this many steps each
+// arc is short, and strays from its chord by far less than the chord's length,
+// so twice the longest step any corner takes is enough; the tests check it.
+fn motion_box(bbox: & Aabb, start: & Placement, end: & Placement) -> Aabb {
+    let corners_at = |step: usize| {
+        let matrix = Placement::interpolate(start, end, step as f64 / MOTION_BOUND_STEPS as f64).matrix();
+        box_corners(bbox).map(|corner| matrix.transform_point(&corner))
+    };
+
+    let mut result = Aabb::new();
+    let mut largest_step: f64 = 0.0;
+    let mut previous = corners_at(0);
+
+    for step in 0..=MOTION_BOUND_STEPS {
+        let corners = corners_at(step);
+        for (corner, previous_corner) in corners.iter().zip(previous.iter()) {
+            result = Aabb::enclosing(&result, &Aabb::from_points(corner, corner));
+            largest_step = largest_step.max((corner - previous_corner).length());
+        }
+        previous = corners;
+    }
+
+    let padding = 2.0 * largest_step;
+    Aabb::build(
+        result.axis_interval(0).expand(padding),
+        result.axis_interval(1).expand(padding),
+        result.axis_interval(2).expand(padding)
+    )
+}
+
+// The direction isn't renormalised, so t means the same in both spaces.
+fn to_object_space(world_to_object: & Matrix4, ray: & Ray) -> Ray {
+    Ray::from(
+        world_to_object.transform_point(ray.origin()),
+        world_to_object.transform_vector(ray.direction())
+    ).with_time(ray.time())
+}
+
+// Brings a hit found in the object's own space back to the world.
+fn hit_to_world(hit: (bool, Option<HitRecord>), object_to_world: & Matrix4, normal_to_world: & Matrix4) -> (bool, Option<HitRecord>) {
+    let mut hit_record = match hit {
+        (true, Some(hit_record)) => hit_record,
+        _ => return (false, None)
+    };
+
+    hit_record.point = object_to_world.transform_point(&hit_record.point);
+    // Normals keep their side relative to the ray, so front_face still holds.
+    hit_record.normal = hit_record.normal
+        .map(|normal| unit_vector(&normal_to_world.transform_vector(&normal)));
+
+    (true, Some(hit_record))
+}
+
+impl Hittable for Transform<'_> {
+    fn hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        let object_ray = to_object_space(&self.world_to_object, ray);
+        hit_to_world(self.object.hit(&object_ray, ray_t, rng), &self.object_to_world, &self.normal_to_world)
+    }
+
+    fn bounding_box(& self) -> Aabb {
+        self.bbox
+    }
+
+    fn surface_hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        let object_ray = to_object_space(&self.world_to_object, ray);
+        hit_to_world(self.object.surface_hit(&object_ray, ray_t, rng), &self.object_to_world, &self.normal_to_world)
+    }
+
+    fn transmittance(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> f64 {
+        self.object.transmittance(&to_object_space(&self.world_to_object, ray), ray_t, rng)
+    }
+}
+
+impl AnimatedTransform<'_> {
+    // Where the object is at `time`, as object_to_world and its inverse.
+    // Held still outside times 0 to 1, so the bounding box stays true.
+    fn matrices_at(& self, time: f64) -> Option<(Matrix4, Matrix4)> {
+        let placement = Placement::interpolate(&self.start, &self.end, time.clamp(0.0, 1.0));
+        let object_to_world = placement.matrix();
+        object_to_world.affine_inverse().map(|world_to_object| (object_to_world, world_to_object))
+    }
+
+    fn closest_hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng, surfaces_only: bool) -> (bool, Option<HitRecord>) {
+        let (object_to_world, world_to_object) = match self.matrices_at(ray.time()) {
+            Some(matrices) => matrices,
+            None => return (false, None)
+        };
+
+        let object_ray = to_object_space(&world_to_object, ray);
+        let hit = if surfaces_only {
+            self.object.surface_hit(&object_ray, ray_t, rng)
+        } else {
+            self.object.hit(&object_ray, ray_t, rng)
+        };
+        hit_to_world(hit, &object_to_world, &world_to_object.transpose())
+    }
+}
+
+impl Hittable for AnimatedTransform<'_> {
+    fn hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        self.closest_hit(ray, ray_t, rng, false)
+    }
+
+    fn bounding_box(& self) -> Aabb {
+        self.bbox
+    }
+
+    fn surface_hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        self.closest_hit(ray, ray_t, rng, true)
+    }
+
+    fn transmittance(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> f64 {
+        match self.matrices_at(ray.time()) {
+            Some((_, world_to_object)) => self.object.transmittance(&to_object_space(&world_to_object, ray), ray_t, rng),
+            None => 1.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::vec3::{ cross_product, dot_product, random_unit_vector };
+    use crate::materials::lambertian::Lambertian;
+    use crate::materials::MatArc;
+
+    use super::super::quad::Quad;
+    use super::super::sphere::Sphere;
+    use super::*;
+
+    fn material() -> MatArc {
+        Arc::new(Box::new(Lambertian::new()))
+    }
+
+    // Translated, rotated and stretched unevenly, so normals need the inverse transpose.
+    fn object_to_world() -> Matrix4 {
+        let rotation = Quaternion::from_axis_angle(&Vector3::build(1.0, 2.0, 0.5), 35.0);
+        Placement::build(&Vector3::build(2.0, 0.5, 1.5), &rotation, &Vector3::build(1.0, -2.0, 3.0)).matrix()
+    }
+
+    fn hit(object: & dyn Hittable, ray: & Ray) -> Option<HitRecord> {
+        match object.hit(ray, &Interval::build(0.001, f64::INFINITY), &mut Prng::seed(0)) {
+            (true, Some(hit_record)) => Some(hit_record),
+            _ => None
+        }
+    }
+
+    // Rays from around the object towards random points near its middle.
+    fn rays(centre: & Point3, count: usize) -> Vec<Ray> {
+        let mut rng = Prng::seed(13);
+        (0..count).map(|_| {
+            let origin = *centre + random_unit_vector(&mut rng) * 8.0;
+            let target = *centre + random_unit_vector(&mut rng) * 0.8;
+            Ray::from(origin, target - origin)
+        }).collect()
+    }
+
+    // A transformed quad is the quad with its corner and edges transformed, built by hand.
+    #[test]
+    fn instance_matches_the_quad_built_in_place() {
+        let matrix = object_to_world();
+        let corner = Point3::build(-1.0, -1.0, 0.0);
+        let (u, v) = (Vector3::build(2.0, 0.0, 0.5), Vector3::build(0.0, 2.0, 1.0));
+
+        let quad: Arc<dyn Hittable> = Arc::new(Quad::build(&corner, &u, &v, &material()));
+        let instance = Transform::build(&quad, &matrix);
+        let in_place = Quad::build(&matrix.transform_point(&corner), &matrix.transform_vector(&u), &matrix.transform_vector(&v), &material());
+
+        let mut hits = 0;
+        for ray in rays(&matrix.transform_point(&Point3::new()), 500) {
+            match (hit(&instance, &ray), hit(&in_place, &ray)) {
+                (Some(a), Some(b)) => {
+                    hits += 1;
+                    assert!((a.t - b.t).abs() < 1e-9, "t {} != {}", a.t, b.t);
+                    assert!((a.point() - b.point()).length() < 1e-9);
+                    assert!((a.normal() - b.normal()).length() < 1e-9, "{:?} != {:?}", a.normal(), b.normal());
+                    assert_eq!(a.front_face(), b.front_face());
+                }
+                (None, None) => {}
+                _ => panic!("only one of them was hit by the ray towards {:?}", ray.direction())
+            }
+        }
+        assert!(hits > 100, "too few rays hit to tell: {}", hits);
+    }
+
+    // On an ellipsoid the normal is unit length and perpendicular to the
+    // surface, which nearby hits trace out.
+    #[test]
+    fn normals_stay_perpendicular_under_uneven_scaling() {
+        let matrix = object_to_world();
+        let sphere: Arc<dyn Hittable> = Arc::new(Sphere::build_explicit((0.0, 0.0, 0.0), 1.0, &material()));
+        let ellipsoid = Transform::build(&sphere, &matrix);
+
+        let nudge = 1e-5;
+        for ray in rays(&matrix.transform_point(&Point3::new()), 200) {
+            let hit_record = match hit(&ellipsoid, &ray) {
+                Some(hit_record) => hit_record,
+                None => continue
+            };
+            let normal = hit_record.normal();
+            assert!((normal.length() - 1.0).abs() < 1e-9);
+            assert!(hit_record.front_face() && dot_product(&normal, ray.direction()) < 0.0);
+
+            let side = unit_vector(&cross_product(ray.direction(), &normal));
+            let neighbours = [side, unit_vector(&cross_product(&side, ray.direction()))].map(|offset| {
+                let nudged = Ray::from(*ray.origin() + offset * nudge, *ray.direction());
+                hit(&ellipsoid, &nudged).map(|hit_record| hit_record.point())
+            });
+            for neighbour in neighbours.into_iter().flatten() {
+                let step = neighbour - hit_record.point();
+                assert!(dot_product(&step, &normal).abs() < 1e-3 * step.length(), "normal isn't perpendicular to the surface");
+            }
+        }
+    }
+
+    // From inside, the ray meets the back of the surface.
+    #[test]
+    fn front_face_is_kept() {
+        let matrix = object_to_world();
+        let sphere: Arc<dyn Hittable> = Arc::new(Sphere::build_explicit((0.0, 0.0, 0.0), 1.0, &material()));
+        let ellipsoid = Transform::build(&sphere, &matrix);
+
+        let inside = Ray::from(matrix.transform_point(&Point3::new()), Vector3::build(0.3, 1.0, -0.2));
+        let hit_record = hit(&ellipsoid, &inside).expect("a ray from the middle hits");
+        assert!(!hit_record.front_face());
+        assert!(dot_product(&hit_record.normal(), inside.direction()) < 0.0);
+    }
+
+    #[test]
+    fn box_encloses_the_transformed_object() {
+        let matrix = object_to_world();
+        let sphere: Arc<dyn Hittable> = Arc::new(Sphere::build_explicit((0.5, -0.5, 0.2), 1.0, &material()));
+        let bbox = Transform::build(&sphere, &matrix).bounding_box();
+
+        let mut rng = Prng::seed(2);
+        for _ in 0..2000 {
+            let point = matrix.transform_point(&(Point3::build(0.5, -0.5, 0.2) + random_unit_vector(&mut rng)));
+            assert!((0..3).all(|axis| bbox.axis_interval(axis).contains(point[axis])), "{:?} is outside the box", point);
+        }
+    }
+
+    fn moving_ball() -> AnimatedTransform<'static> {
+        let sphere: Arc<dyn Hittable> = Arc::new(Sphere::build_explicit((0.0, 0.0, 0.0), 0.5, &material()));
+        let end = Placement::build(
+            &Vector3::build(1.5, 1.5, 1.5),
+            &Quaternion::from_axis_angle(&Vector3::build(0.0, 1.0, 0.0), 90.0),
+            &Vector3::build(4.0, 0.0, 0.0)
+        );
+        AnimatedTransform::build(&sphere, &Placement::new(), &end)
+    }
+
+    #[test]
+    fn moves_from_the_start_placement_to_the_end_one() {
+        let ball = moving_ball();
+        let towards_start = |time: f64| Ray::from(Point3::build(0.0, 0.0, 5.0), Vector3::build(0.0, 0.0, -1.0)).with_time(time);
+        let towards_end = |time: f64| Ray::from(Point3::build(4.0, 0.0, 5.0), Vector3::build(0.0, 0.0, -1.0)).with_time(time);
+
+        assert!(hit(&ball, &towards_start(0.0)).is_some());
+        assert!(hit(&ball, &towards_end(0.0)).is_none());
+        assert!(hit(&ball, &towards_start(1.0)).is_none());
+
+        let end_hit = hit(&ball, &towards_end(1.0)).expect("the ball is at the end placement at time 1");
+        // Scaled by 1.5, so its front is 0.75 from the centre.
+        assert!((end_hit.point().z() - 0.75).abs() < 1e-9);
+    }
+
+    // The corners of an object swinging round on a wide arc bulge past the
+    // box of the sampled times between samples, but never past the padding.
+    #[test]
+    fn motion_box_holds_the_object_between_samples() {
+        let object_box = Aabb::from_points(&Point3::build(2.5, -0.5, -0.5), &Point3::build(3.5, 0.5, 0.5));
+        let start = Placement::new();
+        let end = Placement::build(
+            &Vector3::build(0.5, 2.0, 1.0),
+            &Quaternion::from_axis_angle(&Vector3::build(0.2, 1.0, 0.0), 170.0),
+            &Vector3::build(-1.0, 2.0, 0.5)
+        );
+        let bbox = motion_box(&object_box, &start, &end);
+
+        let corners_at = |time: f64| {
+            let matrix = Placement::interpolate(&start, &end, time).matrix();
+            box_corners(&object_box).map(|corner| matrix.transform_point(&corner))
+        };
+        let inside = |bbox: & Aabb, point: & Point3| (0..3).all(|axis| bbox.axis_interval(axis).contains(point[axis]));
+
+        let mut sampled = Aabb::new();
+        for step in 0..=MOTION_BOUND_STEPS {
+            for corner in corners_at(step as f64 / MOTION_BOUND_STEPS as f64) {
+                sampled = Aabb::enclosing(&sampled, &Aabb::from_points(&corner, &corner));
+            }
+        }
+
+        let mut bulges = false;
+        for index in 0..=10000 {
+            for corner in corners_at(index as f64 / 10000.0) {
+                assert!(inside(&bbox, &corner), "{:?} is outside the motion box", corner);
+                bulges |= !inside(&sampled, &corner);
+            }
+        }
+        assert!(bulges, "the path should leave the box of the sampled times somewhere");
+    }
+}
@@ -0,0 +1,205 @@
+use std::sync::Arc;
+
+use crate::common::interval::Interval;
+use crate::common::random::{ random_f64_standard, Prng };
+use crate::common::ray::Ray;
+use crate::common::vec3::{ cross_product, dot_product, unit_vector, Point3, Vector3 };
+use crate::common::RAY_MINIMUM_DISTANCE_BEFORE_HIT;
+
+use crate::materials::MatArc;
+
+use super::aabb::Aabb;
+use super::hittable::{ HitRecord, Hittable };
+
+// Rays closer than this to parallel with the plane are treated as misses.
+const PARALLEL_CUTOFF: f64 = 1e-12;
+
+pub struct Triangle {
+    vertices: [Point3; 3],
+    material: MatArc,
+
+    // Optional per-vertex shading normals and texture coordinates, as loaded from meshes.
+    vertex_normals: Option<[Vector3; 3]>,
+    vertex_uvs: Option<[(f64, f64); 3]>,
+
+    // Precomputed edges from the first vertex, and the outward facing normal
+    // given by their winding.
+    edge_1: Vector3,
+    edge_2: Vector3,
+    normal: Vector3,
+    area: f64
+}
+
+impl Triangle {
+    pub fn build(a: & Point3, b: & Point3, c: & Point3, material: & MatArc) -> Triangle {
+        Triangle::from(*a, *b, *c, Arc::clone(material))
+    }
+
+    pub fn from(a: Point3, b: Point3, c: Point3, material: MatArc) -> Triangle {
+        let edge_1 = b - a;
+        let edge_2 = c - a;
+        let n = cross_product(&edge_1, &edge_2);
+
+        Triangle {
+            vertices: [a, b, c],
+            material,
+            vertex_normals: None,
+            vertex_uvs: None,
+            edge_1,
+            edge_2,
+            normal: unit_vector(&n),
+            area: n.length() / 2.0
+        }
+    }
+
+    pub fn with_vertex_normals(mut self, normals: [Vector3; 3]) -> Triangle {
+        self.vertex_normals = Some(normals.map(|normal| unit_vector(&normal)));
+        self
+    }
+
+    pub fn with_vertex_uvs(mut self, uvs: [(f64, f64); 3]) -> Triangle {
+        self.vertex_uvs = Some(uvs);
+        self
+    }
+}
+
+impl Hittable for Triangle {
+    // Möller–Trumbore: solve for t and the barycentric coordinates of the hit at once.
+    fn hit(& self, ray: & Ray, ray_t: & Interval, _rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        let p = cross_product(ray.direction(), &self.edge_2);
+        let determinant = dot_product(&self.edge_1, &p);
+        if determinant.abs() < PARALLEL_CUTOFF {
+            return (false, None)
+        }
+
+        let inverse_determinant = 1.0 / determinant;
+        let s = ray.origin() - &self.vertices[0];
+        let u = dot_product(&s, &p) * inverse_determinant;
+        if !(0.0..=1.0).contains(&u) {
+            return (false, None)
+        }
+
+        let q = cross_product(&s, &self.edge_1);
+        let v = dot_product(ray.direction(), &q) * inverse_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            return (false, None)
+        }
+
+        let t = dot_product(&self.edge_2, &q) * inverse_determinant;
+        if !ray_t.surrounds(t) {
+            return (false, None)
+        }
+
+        // Barycentric weights of the three vertices.
+        let weights = [1.0 - u - v, u, v];
+
+        let (surface_u, surface_v) = match &self.vertex_uvs {
+            Some(uvs) => (
+                weights[0] * uvs[0].0 + weights[1] * uvs[1].0 + weights[2] * uvs[2].0,
+                weights[0] * uvs[0].1 + weights[1] * uvs[1].1 + weights[2] * uvs[2].1
+            ),
+            None => (u, v)
+        };
+
+        let mut hit_record = HitRecord {
+            t,
+            normal: None,
+            material: Arc::clone(&self.material),
+            point: ray.at(&t),
+            u: surface_u,
+            v: surface_v,
+            front_face: None,
+            object_id: 0,
+            material_id: 0
+        };
+
+        // The side is decided by the triangle itself, since interpolated normals
+        // can lean past the ray near silhouettes, and then the shading normal
+        // is turned to the same side.
+        hit_record.set_face_normal(ray, &self.normal);
+        if let Some(normals) = &self.vertex_normals {
+            let shading_normal = unit_vector(&(normals[0] * weights[0] + normals[1] * weights[1] + normals[2] * weights[2]));
+            let facing = hit_record.normal();
+            hit_record.normal = Some(if dot_product(&shading_normal, &facing) < 0.0 { -shading_normal } else { shading_normal });
+        }
+
+        (true, Some(hit_record))
+    }
+
+    fn bounding_box(& self) -> Aabb {
+        let [a, b, c] = &self.vertices;
+        Aabb::enclosing(&Aabb::from_points(a, b), &Aabb::from_points(c, c))
+    }
+
+    fn pdf_value(& self, ray: & Ray, rng: &mut Prng) -> f64 {
+        let hit_record = match self.hit(ray, &Interval::build(RAY_MINIMUM_DISTANCE_BEFORE_HIT, f64::INFINITY), rng) {
+            (true, Some(hit_record)) => hit_record,
+            _ => return 0.0
+        };
+
+        let length = ray.direction().length();
+        let distance_squared = hit_record.t * hit_record.t * ray.direction().length_squared();
+        let cosine = (dot_product(ray.direction(), &self.normal) / length).abs();
+
+        distance_squared / (cosine * self.area)
+    }
+
+    // A uniform point on the parallelogram of the two edges, folded back into
+    // the triangle if it lands in the other half.
+    fn random_direction(& self, origin: & Point3, _time: f64, rng: &mut Prng) -> Vector3 {
+        let (mut a, mut b) = (random_f64_standard(rng), random_f64_standard(rng));
+        if a + b > 1.0 {
+            (a, b) = (1.0 - a, 1.0 - b);
+        }
+        let point = self.vertices[0] + self.edge_1 * a + self.edge_2 * b;
+        point - *origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::materials::lambertian::Lambertian;
+
+    use super::*;
+
+    #[test]
+    fn side_follows_the_triangle_not_the_shading_normal() {
+        let material: MatArc = Arc::new(Box::new(Lambertian::new()));
+        let tilted = Vector3::build(1.0, 0.0, 0.1);
+        let triangle = Triangle::build(&Point3::build(0.0, 0.0, 0.0), &Point3::build(1.0, 0.0, 0.0), &Point3::build(0.0, 1.0, 0.0), &material)
+            .with_vertex_normals([tilted, tilted, tilted]);
+
+        // Grazing the front, but heading the same way as the shading normal.
+        let ray = Ray::from(Point3::build(-1.0, 0.25, 0.06), Vector3::build(1.0, 0.0, -0.05));
+        let hit_rec = match triangle.hit(&ray, &Interval::build(0.001, f64::INFINITY), &mut Prng::seed(0)) {
+            (true, Some(hit_rec)) => hit_rec,
+            _ => panic!("ray should hit the triangle")
+        };
+
+        assert!(hit_rec.front_face());
+        let normal = hit_rec.normal();
+        assert!(normal.z() > 0.0 && normal.x() > 0.9, "shading normal should face the ray's side: {:?}", normal);
+    }
+
+    // The density over all directions integrates to one, and sampled
+    // directions land on the triangle.
+    #[test]
+    fn light_sampling_density_is_normalised() {
+        use crate::common::vec3::random_unit_vector;
+
+        let material: MatArc = Arc::new(Box::new(Lambertian::new()));
+        let triangle = Triangle::build(&Point3::build(-1.0, 2.0, -1.0), &Point3::build(2.0, 2.5, 0.0), &Point3::build(0.0, 1.5, 2.0), &material);
+        let origin = Point3::new();
+        let mut rng = Prng::seed(5);
+
+        let count = 200000;
+        let total: f64 = (0..count).map(|_| triangle.pdf_value(&Ray::from(origin, random_unit_vector(&mut rng)), &mut rng)).sum();
+        let integral = total * 4.0 * std::f64::consts::PI / count as f64;
+        assert!((integral - 1.0).abs() < 0.05, "density integrates to {}", integral);
+
+        for _ in 0..100 {
+            let direction = triangle.random_direction(&origin, 0.0, &mut rng);
+            assert!(triangle.pdf_value(&Ray::from(origin, direction), &mut rng) > 0.0);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use crate::common::interval::Interval;
+use crate::common::random::Prng;
+use crate::common::ray::Ray;
+
+use crate::materials::MatArc;
+
+use super::aabb::Aabb;
+use super::bvh::BvhNode;
+use super::hittable::{ HitRecord, Hittable };
+use super::obj::{ load_triangles, ObjError };
+use super::triangle::Triangle;
+
+// A set of triangles behind its own bounding volume hierarchy, so a mesh is a
+// single object to the scene around it.
+pub struct TriangleMesh {
+    triangles: BvhNode<'static>
+}
+
+impl TriangleMesh {
+    pub fn build(triangles: Vec<Triangle>) -> TriangleMesh {
+        let objects: Vec<Box<dyn Hittable>> = triangles.into_iter()
+            .map(|triangle| Box::new(triangle) as Box<dyn Hittable>)
+            .collect();
+
+        TriangleMesh {
+            triangles: BvhNode::build(objects)
+        }
+    }
+
+    // Faces without a material of their own from the OBJ's MTL files use default_material.
+    // Also returns the MTL files that couldn't be read, which were left out.
+    pub fn load_obj(path: & Path, default_material: & MatArc) -> Result<(TriangleMesh, Vec<ObjError>), ObjError> {
+        let (triangles, warnings) = load_triangles(path, default_material)?;
+
+        if triangles.is_empty() {
+            return Err(ObjError::NoFaces(path.to_path_buf()));
+        }
+
+        Ok((TriangleMesh::build(triangles), warnings))
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(& self, ray: & Ray, ray_t: & Interval, rng: &mut Prng) -> (bool, Option<HitRecord>) {
+        self.triangles.hit(ray, ray_t, rng)
+    }
+
+    fn bounding_box(& self) -> Aabb {
+        self.triangles.bounding_box()
+    }
+}
@@ -0,0 +1,862 @@
+use std::collections::{ HashMap, HashSet };
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{ Path, PathBuf };
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::common::colour::Colour;
+use crate::common::quaternion::Quaternion;
+use crate::common::vec3::Vector3;
+
+use crate::engine::camera::{ Background, Camera, DEFAULT_ROULETTE_DEPTH };
+use crate::engine::adaptive::AdaptiveSampling;
+use crate::engine::denoiser::Denoiser;
+use crate::engine::integrator::{ AmbientOcclusion, DepthShader, Integrator, NormalShader, Whitted };
+
+use crate::materials::dielectric::Dialectric;
+use crate::materials::diffuse_light::DiffuseLight;
+use crate::materials::henyey_greenstein::HenyeyGreenstein;
+use crate::materials::isotropic::Isotropic;
+use crate::materials::lambertian::Lambertian;
+use crate::materials::metal::Metal;
+use crate::materials::MatArc;
+
+use crate::primitive::constant_medium::ConstantMedium;
+use crate::primitive::density_grid::{ DensityGrid, GridError, SampleType };
+use crate::primitive::grid_volume::GridVolume;
+use crate::primitive::hittable::Hittable;
+use crate::primitive::hittable_list::HittableList;
+use crate::primitive::quad::{ make_box, Quad };
+use crate::primitive::sphere::Sphere;
+use crate::primitive::tagged::Tagged;
+use crate::primitive::transform::{ AnimatedTransform, Placement, Transform };
+use crate::primitive::triangle::Triangle;
+use crate::primitive::triangle_mesh::TriangleMesh;
+
+use crate::textures::checker::Checker;
+use crate::textures::image_texture::{ ImageTexture, WrapMode };
+use crate::textures::noise_texture::{ NoisePattern, NoiseTexture };
+use crate::textures::{ solid_texture, TexArc };
+
+// A scene loaded from a TOML description: the camera to render it with, and
+// everything in it.
+pub struct Scene {
+    pub camera: Camera,
+    pub world: HittableList<'static>,
+    // The emissive spheres, quads, triangles and boxes in the world, shared with
+    // it, for the camera to sample directly.
+    pub lights: HittableList<'static>,
+    // Problems that didn't stop the scene loading, for the caller to report.
+    pub warnings: Vec<String>
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(PathBuf, io::Error),
+    // Not valid TOML, or not the shape of a scene, located by line and column.
+    Parse { path: PathBuf, line: usize, column: usize, message: String },
+    // Well formed, but refers to something that doesn't exist. `context` says where, e.g. "objects[2]".
+    Invalid { path: PathBuf, context: String, message: String }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            SceneError::Parse { path, line, column, message } =>
+                write!(f, "{}:{}:{}: {}", path.display(), line, column, message),
+            SceneError::Invalid { path, context, message } =>
+                write!(f, "{}: {}: {}", path.display(), context, message)
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+
+// File layout.
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDescription {
+    #[serde(default)]
+    camera: CameraDescription,
+    #[serde(default)]
+    textures: HashMap<String, TextureDescription>,
+    #[serde(default)]
+    materials: HashMap<String, MaterialDescription>,
+    // Objects that aren't in the scene themselves, only placed by instances.
+    #[serde(default)]
+    shapes: HashMap<String, ObjectDescription>,
+    #[serde(default)]
+    objects: Vec<ObjectDescription>
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CameraDescription {
+    aspect_ratio: f64,
+    image_width: i32,
+    samples_per_pixel: i32,
+    max_depth: i32,
+    roulette_depth: i32,
+    vertical_fov: f64,
+    lookfrom: [f64; 3],
+    lookat: [f64; 3],
+    vup: [f64; 3],
+    defocus_angle: f64,
+    focus_dist: f64,
+    // Objects move over times 0 to 1, and rays are sent at times in between these.
+    shutter_open: f64,
+    shutter_close: f64,
+    background: BackgroundDescription,
+    // The path tracer, using max_depth and roulette_depth, when left out.
+    integrator: Option<IntegratorDescription>,
+    // Runs the default denoiser over the finished image.
+    denoise: bool,
+    // With a threshold, pixels stop being sampled once settled, after at least
+    // min_samples; samples_per_pixel is the most any gets.
+    adaptive_threshold: Option<f64>,
+    min_samples: i32,
+    seed: u64
+}
+
+impl Default for CameraDescription {
+    fn default() -> CameraDescription {
+        CameraDescription {
+            aspect_ratio: 16.0 / 9.0,
+            image_width: 400,
+            samples_per_pixel: 100,
+            max_depth: 50,
+            roulette_depth: DEFAULT_ROULETTE_DEPTH,
+            vertical_fov: 90.0,
+            lookfrom: [0.0, 0.0, 0.0],
+            lookat: [0.0, 0.0, -1.0],
+            vup: [0.0, 1.0, 0.0],
+            defocus_angle: 0.0,
+            focus_dist: 10.0,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            background: BackgroundDescription::Named("sky".to_string()),
+            integrator: None,
+            denoise: false,
+            adaptive_threshold: None,
+            min_samples: AdaptiveSampling::new().min_samples(),
+            seed: 0
+        }
+    }
+}
+
+// Either "sky" or an [r, g, b] colour.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BackgroundDescription {
+    Named(String),
+    Solid([f64; 3])
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum IntegratorDescription {
+    Path,
+    // Follows mirrors and glass up to max_depth, the camera's by default.
+    Whitted { max_depth: Option<i32> },
+    Normals,
+    // Occluders further off than `distance` are ignored; all of them count by default.
+    AmbientOcclusion { distance: Option<f64> },
+    // Maps distances up to `max_distance` to white through black, or writes them as they are.
+    Depth { max_distance: Option<f64> }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDescription {
+    Solid { colour: [f64; 3] },
+    Checker { scale: f64, even: [f64; 3], odd: [f64; 3] },
+    // PNG or JPEG, relative to the scene file.
+    Image { path: String, #[serde(default)] wrap: WrapDescription },
+    Noise {
+        scale: f64,
+        #[serde(default = "white")] colour: [f64; 3],
+        #[serde(default)] pattern: PatternDescription,
+        #[serde(default)] seed: u64
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum WrapDescription {
+    #[default]
+    Repeat,
+    Mirror,
+    Clamp
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum PatternDescription {
+    Smooth,
+    Turbulence,
+    #[default]
+    Marble,
+    Wood,
+    Clouds,
+    Ridged,
+    Cells
+}
+
+// Either an [r, g, b] colour or the name of one of the [textures].
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColourOrTexture {
+    Colour([f64; 3]),
+    Texture(String)
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDescription {
+    Lambertian { albedo: ColourOrTexture },
+    Metal { albedo: ColourOrTexture, #[serde(default)] fuzz: f64 },
+    Dielectric {
+        refractive_index: f64,
+        #[serde(default = "clear")] albedo: ColourOrTexture,
+        // Beer–Lambert coefficient per unit distance inside, per channel.
+        #[serde(default)] absorption: [f64; 3]
+    },
+    DiffuseLight { emit: ColourOrTexture },
+    Isotropic { albedo: ColourOrTexture },
+    HenyeyGreenstein { albedo: ColourOrTexture, g: f64 }
+}
+
+fn white() -> [f64; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn clear() -> ColourOrTexture {
+    ColourOrTexture::Colour(white())
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDescription {
+    // Moves in a straight line from `center` at time 0 to `end_center` at time 1, if given.
+    Sphere { center: [f64; 3], radius: f64, material: String, end_center: Option<[f64; 3]> },
+    Quad { corner: [f64; 3], u: [f64; 3], v: [f64; 3], material: String },
+    Triangle { a: [f64; 3], b: [f64; 3], c: [f64; 3], material: String },
+    Box { a: [f64; 3], b: [f64; 3], material: String },
+    // Faces without an MTL material of their own use `material`, or grey if it is left out.
+    Mesh { path: String, material: Option<String> },
+    // Fills the boundary object, whose own material is ignored, with a volume
+    // scattering through `material`, normally an isotropic or henyey_greenstein one.
+    ConstantMedium { boundary: Box<ObjectDescription>, density: f64, material: String },
+    // A volume of varying density filling the box between corners a and b.
+    // `density` scales every value of the grid.
+    GridVolume {
+        a: [f64; 3],
+        b: [f64; 3],
+        grid: GridDescription,
+        #[serde(default = "one")] density: f64,
+        material: String
+    },
+    // Places one of the [shapes]: scaled first, then rotated about x, y and z
+    // in that order (in degrees), then translated. With an `end`, it moves from
+    // this placement at time 0 to that one at time 1.
+    Instance {
+        shape: String,
+        #[serde(default)] translate: [f64; 3],
+        #[serde(default)] rotate: [f64; 3],
+        #[serde(default)] scale: ScaleDescription,
+        end: Option<EndPlacementDescription>
+    }
+}
+
+// Anything left out stays as it was at the start.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EndPlacementDescription {
+    translate: Option<[f64; 3]>,
+    rotate: Option<[f64; 3]>,
+    scale: Option<ScaleDescription>
+}
+
+// Either one factor for every axis or one per axis.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScaleDescription {
+    Uniform(f64),
+    PerAxis([f64; 3])
+}
+
+impl Default for ScaleDescription {
+    fn default() -> ScaleDescription {
+        ScaleDescription::Uniform(1.0)
+    }
+}
+
+fn one() -> f64 {
+    1.0
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum GridDescription {
+    // A headerless file of little-endian samples, x varying fastest, relative to the scene file.
+    Raw { path: String, resolution: [usize; 3], #[serde(default)] sample_type: SampleTypeDescription },
+    // A cloud generated from fBm noise.
+    Noise {
+        resolution: [usize; 3],
+        #[serde(default = "default_grid_frequency")] frequency: f64,
+        #[serde(default = "default_grid_octaves")] octaves: usize,
+        #[serde(default)] threshold: f64,
+        #[serde(default)] seed: u64
+    }
+}
+
+fn default_grid_frequency() -> f64 {
+    4.0
+}
+
+fn default_grid_octaves() -> usize {
+    5
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum SampleTypeDescription {
+    U8,
+    U16,
+    #[default]
+    F32
+}
+
+
+// Loading.
+
+fn to_vector(values: & [f64; 3]) -> Vector3 {
+    Vector3::build(values[0], values[1], values[2])
+}
+
+// 1-based line and column of a byte offset into the source.
+fn line_and_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |newline| newline + 1) + 1;
+    (line, column)
+}
+
+struct Loader<'a> {
+    path: &'a Path,
+    textures: HashMap<String, TexArc>,
+    materials: HashMap<String, MatArc>,
+    // Names of the diffuse_light materials.
+    emissive: HashSet<String>,
+    // Numbered from 1 in order of name, for the material ID AOV.
+    material_ids: HashMap<String, usize>,
+    shape_descriptions: &'a HashMap<String, ObjectDescription>,
+    shapes: HashMap<String, Arc<dyn Hittable>>,
+    warnings: Vec<String>
+}
+
+impl Loader<'_> {
+    fn error(&self, context: &str, message: String) -> SceneError {
+        SceneError::Invalid { path: self.path.to_path_buf(), context: context.to_string(), message }
+    }
+
+    // An error at `context` unless `value` is above zero; zero or negative sizes
+    // and densities would panic or loop forever once rendering started.
+    fn positive(&self, context: &str, value: f64) -> Result<(), SceneError> {
+        if value > 0.0 && value.is_finite() {
+            Ok(())
+        } else {
+            Err(self.error(context, format!("must be greater than zero, not {}", value)))
+        }
+    }
+
+    fn material(&self, context: &str, name: &str) -> Result<MatArc, SceneError> {
+        match self.materials.get(name) {
+            Some(material) => Ok(Arc::clone(material)),
+            None => Err(self.error(context, format!("unknown material '{}'", name)))
+        }
+    }
+
+    // Relative paths in the scene are relative to the scene file.
+    fn resolve_path(&self, path: &str) -> PathBuf {
+        self.path.parent().unwrap_or(Path::new("")).join(path)
+    }
+
+    fn build_texture(&self, name: &str, description: & TextureDescription) -> Result<TexArc, SceneError> {
+        let texture: TexArc = match description {
+            TextureDescription::Solid { colour } => solid_texture(to_vector(colour)),
+            TextureDescription::Checker { scale, even, odd } => {
+                self.positive(&format!("textures.{}.scale", name), *scale)?;
+                Arc::new(Box::new(Checker::build_colours(*scale, to_vector(even), to_vector(odd))))
+            }
+            TextureDescription::Image { path, wrap } => {
+                let wrap = match wrap {
+                    WrapDescription::Repeat => WrapMode::Repeat,
+                    WrapDescription::Mirror => WrapMode::Mirror,
+                    WrapDescription::Clamp => WrapMode::Clamp
+                };
+                let image = ImageTexture::load(&self.resolve_path(path), wrap)
+                    .map_err(|error| self.error(&format!("textures.{}", name), error.to_string()))?;
+                Arc::new(Box::new(image))
+            }
+            TextureDescription::Noise { scale, colour, pattern, seed } => {
+                let pattern = match pattern {
+                    PatternDescription::Smooth => NoisePattern::Smooth,
+                    PatternDescription::Turbulence => NoisePattern::Turbulence,
+                    PatternDescription::Marble => NoisePattern::Marble,
+                    PatternDescription::Wood => NoisePattern::Wood,
+                    PatternDescription::Clouds => NoisePattern::Clouds,
+                    PatternDescription::Ridged => NoisePattern::Ridged,
+                    PatternDescription::Cells => NoisePattern::Cells
+                };
+                Arc::new(Box::new(NoiseTexture::from(*scale, to_vector(colour), pattern, *seed)))
+            }
+        };
+
+        Ok(texture)
+    }
+
+    fn texture(&self, context: &str, value: & ColourOrTexture) -> Result<TexArc, SceneError> {
+        match value {
+            ColourOrTexture::Colour(colour) => Ok(solid_texture(to_vector(colour))),
+            ColourOrTexture::Texture(name) => match self.textures.get(name) {
+                Some(texture) => Ok(Arc::clone(texture)),
+                None => Err(self.error(context, format!("unknown texture '{}'", name)))
+            }
+        }
+    }
+
+    fn build_material(&self, name: &str, description: & MaterialDescription) -> Result<MatArc, SceneError> {
+        let context = format!("materials.{}", name);
+
+        let material: MatArc = match description {
+            MaterialDescription::Lambertian { albedo } =>
+                Arc::new(Box::new(Lambertian::from_texture(self.texture(&context, albedo)?))),
+            MaterialDescription::Metal { albedo, fuzz } =>
+                Arc::new(Box::new(Metal::from_texture(self.texture(&context, albedo)?, *fuzz))),
+            MaterialDescription::Dielectric { refractive_index, albedo, absorption } => {
+                let dielectric = Dialectric::from_texture(self.texture(&context, albedo)?, *refractive_index)
+                    .with_absorption(to_vector(absorption));
+                Arc::new(Box::new(dielectric))
+            }
+            MaterialDescription::DiffuseLight { emit } =>
+                Arc::new(Box::new(DiffuseLight::from_texture(self.texture(&context, emit)?))),
+            MaterialDescription::Isotropic { albedo } =>
+                Arc::new(Box::new(Isotropic::from_texture(self.texture(&context, albedo)?))),
+            MaterialDescription::HenyeyGreenstein { albedo, g } =>
+                Arc::new(Box::new(HenyeyGreenstein::from_texture(self.texture(&context, albedo)?, *g)))
+        };
+
+        Ok(material)
+    }
+
+    fn camera(&self, description: & CameraDescription) -> Result<Camera, SceneError> {
+        let background = match &description.background {
+            BackgroundDescription::Named(name) if name == "sky" => Background::Sky,
+            BackgroundDescription::Named(name) => return Err(self.error(
+                "camera.background",
+                format!("unknown background '{}', expected \"sky\" or an [r, g, b] colour", name)
+            )),
+            BackgroundDescription::Solid(colour) => Background::Solid(to_vector(colour))
+        };
+
+        self.positive("camera.aspect_ratio", description.aspect_ratio)?;
+        self.positive("camera.image_width", description.image_width as f64)?;
+        self.positive("camera.samples_per_pixel", description.samples_per_pixel as f64)?;
+        self.positive("camera.max_depth", description.max_depth as f64)?;
+
+        let mut camera = Camera::build(
+            description.aspect_ratio,
+            description.image_width,
+            description.samples_per_pixel,
+            description.max_depth,
+            description.vertical_fov,
+            to_vector(&description.lookfrom),
+            to_vector(&description.lookat),
+            to_vector(&description.vup),
+            description.defocus_angle,
+            description.focus_dist
+        );
+        camera.set_roulette_depth(description.roulette_depth);
+        camera.set_shutter(description.shutter_open, description.shutter_close);
+        camera.set_background(background);
+        camera.set_seed(description.seed);
+
+        let integrator: Option<Box<dyn Integrator>> = match &description.integrator {
+            None | Some(IntegratorDescription::Path) => None,
+            Some(IntegratorDescription::Whitted { max_depth }) =>
+                Some(Box::new(Whitted::build(max_depth.unwrap_or(description.max_depth)))),
+            Some(IntegratorDescription::Normals) => Some(Box::new(NormalShader::new())),
+            Some(IntegratorDescription::AmbientOcclusion { distance }) =>
+                Some(Box::new(distance.map_or(AmbientOcclusion::new(), AmbientOcclusion::build))),
+            Some(IntegratorDescription::Depth { max_distance }) =>
+                Some(Box::new(max_distance.map_or(DepthShader::new(), DepthShader::build)))
+        };
+        camera.set_integrator(integrator);
+        if let Some(threshold) = description.adaptive_threshold {
+            self.positive("camera.adaptive_threshold", threshold)?;
+            camera.set_adaptive(Some(AdaptiveSampling::build(description.min_samples, threshold)));
+        }
+        if description.denoise {
+            camera.set_denoiser(Some(Denoiser::new()));
+        }
+
+        Ok(camera)
+    }
+
+    // Spheres, quads, triangles and boxes that give off light can be sampled as lights.
+    fn is_light(&self, description: & ObjectDescription) -> bool {
+        match description {
+            ObjectDescription::Sphere { material, .. }
+            | ObjectDescription::Quad { material, .. }
+            | ObjectDescription::Triangle { material, .. }
+            | ObjectDescription::Box { material, .. } => self.emissive.contains(material),
+            _ => false
+        }
+    }
+
+    // Whether an object gives off light at all, sampled or not.
+    fn is_emissive(&self, description: & ObjectDescription) -> bool {
+        match description {
+            ObjectDescription::Mesh { material: Some(material), .. } => self.emissive.contains(material),
+            ObjectDescription::Instance { shape, .. } =>
+                self.shape_descriptions.get(shape).is_some_and(|shape| self.is_emissive(shape)),
+            _ => self.is_light(description)
+        }
+    }
+
+    // The ID of the material an object names, or 0 if it names none.
+    fn material_id(&self, description: & ObjectDescription) -> usize {
+        let name = match description {
+            ObjectDescription::Sphere { material, .. }
+            | ObjectDescription::Quad { material, .. }
+            | ObjectDescription::Triangle { material, .. }
+            | ObjectDescription::Box { material, .. }
+            | ObjectDescription::ConstantMedium { material, .. }
+            | ObjectDescription::GridVolume { material, .. } => Some(material),
+            ObjectDescription::Mesh { material, .. } => material.as_ref(),
+            ObjectDescription::Instance { shape, .. } =>
+                return self.shape_descriptions.get(shape).map_or(0, |shape| self.material_id(shape))
+        };
+        name.and_then(|name| self.material_ids.get(name)).copied().unwrap_or(0)
+    }
+
+    fn placement(&self, context: &str, translate: & [f64; 3], rotate: & [f64; 3], scale: & ScaleDescription) -> Result<Placement, SceneError> {
+        let scale = match scale {
+            ScaleDescription::Uniform(factor) => Vector3::build(*factor, *factor, *factor),
+            ScaleDescription::PerAxis(factors) => to_vector(factors)
+        };
+        if scale.x() == 0.0 || scale.y() == 0.0 || scale.z() == 0.0 {
+            return Err(self.error(context, "scale must not be zero".to_string()))
+        }
+
+        let axis_rotation = |axis: Vector3, degrees: f64| Quaternion::from_axis_angle(&axis, degrees);
+        let rotation = axis_rotation(Vector3::build(0.0, 0.0, 1.0), rotate[2])
+            * axis_rotation(Vector3::build(0.0, 1.0, 0.0), rotate[1])
+            * axis_rotation(Vector3::build(1.0, 0.0, 0.0), rotate[0]);
+
+        Ok(Placement::build(&scale, &rotation, &to_vector(translate)))
+    }
+
+    fn build_grid(&self, description: & GridDescription) -> Result<DensityGrid, GridError> {
+        match description {
+            GridDescription::Raw { path, resolution, sample_type } => {
+                let sample_type = match sample_type {
+                    SampleTypeDescription::U8 => SampleType::U8,
+                    SampleTypeDescription::U16 => SampleType::U16,
+                    SampleTypeDescription::F32 => SampleType::F32
+                };
+                DensityGrid::load_raw(&self.resolve_path(path), *resolution, sample_type)
+            }
+            GridDescription::Noise { resolution, frequency, octaves, threshold, seed } =>
+                DensityGrid::from_noise(*resolution, *frequency, *octaves, *threshold, *seed)
+        }
+    }
+
+    // `context` locates the object for error messages, e.g. "objects[2]".
+    fn build_object(&mut self, context: &str, description: & ObjectDescription) -> Result<Box<dyn Hittable>, SceneError> {
+        let object: Box<dyn Hittable> = match description {
+            ObjectDescription::Sphere { center, radius, material, end_center } => {
+                self.positive(&format!("{}.radius", context), *radius)?;
+                let sphere = Sphere::build(&to_vector(center), radius, &self.material(context, material)?);
+                match end_center {
+                    Some(end_center) => Box::new(sphere.with_motion(&to_vector(end_center))),
+                    None => Box::new(sphere)
+                }
+            }
+            ObjectDescription::Quad { corner, u, v, material } =>
+                Box::new(Quad::build(&to_vector(corner), &to_vector(u), &to_vector(v), &self.material(context, material)?)),
+            ObjectDescription::Triangle { a, b, c, material } =>
+                Box::new(Triangle::build(&to_vector(a), &to_vector(b), &to_vector(c), &self.material(context, material)?)),
+            ObjectDescription::Box { a, b, material } =>
+                Box::new(make_box(&to_vector(a), &to_vector(b), &self.material(context, material)?)),
+            ObjectDescription::Mesh { path, material } => {
+                let default_material: MatArc = match material {
+                    Some(material) => self.material(context, material)?,
+                    None => Arc::new(Box::new(Lambertian::from(Colour::build(0.5, 0.5, 0.5))))
+                };
+
+                let (mesh, warnings) = TriangleMesh::load_obj(&self.resolve_path(path), &default_material)
+                    .map_err(|error| self.error(context, error.to_string()))?;
+                for warning in warnings {
+                    let message = format!("{}; its materials are replaced by the default", warning);
+                    self.warnings.push(self.error(context, message).to_string());
+                }
+                Box::new(mesh)
+            }
+            ObjectDescription::ConstantMedium { boundary, density, material } => {
+                self.positive(&format!("{}.density", context), *density)?;
+                let boundary = self.build_object(&format!("{}.boundary", context), boundary)?;
+                Box::new(ConstantMedium::from(boundary, *density, self.material(context, material)?))
+            }
+            ObjectDescription::GridVolume { a, b, grid, density, material } => {
+                self.positive(&format!("{}.density", context), *density)?;
+                let grid = self.build_grid(grid).map_err(|error| self.error(&format!("{}.grid", context), error.to_string()))?;
+                Box::new(GridVolume::build(&Arc::new(grid), &to_vector(a), &to_vector(b), *density, &self.material(context, material)?))
+            }
+            ObjectDescription::Instance { shape, translate, rotate, scale, end } => {
+                let shape = match self.shapes.get(shape) {
+                    Some(shape) => shape,
+                    None => return Err(self.error(context, format!("unknown shape '{}'", shape)))
+                };
+
+                let start = self.placement(context, translate, rotate, scale)?;
+                match end {
+                    Some(end) => {
+                        let end = self.placement(
+                            &format!("{}.end", context),
+                            end.translate.as_ref().unwrap_or(translate),
+                            end.rotate.as_ref().unwrap_or(rotate),
+                            end.scale.as_ref().unwrap_or(scale)
+                        )?;
+                        Box::new(AnimatedTransform::build(shape, &start, &end))
+                    }
+                    None => Box::new(Transform::build(shape, &start.matrix()))
+                }
+            }
+        };
+
+        Ok(object)
+    }
+}
+
+impl Scene {
+    pub fn load(path: & Path) -> Result<Scene, SceneError> {
+        let source = fs::read_to_string(path).map_err(|error| SceneError::Io(path.to_path_buf(), error))?;
+        Scene::parse(&source, path)
+    }
+
+    // `path` is only used for error messages and to find meshes.
+    pub fn parse(source: &str, path: & Path) -> Result<Scene, SceneError> {
+        let description: SceneDescription = toml::from_str(source).map_err(|error| {
+            let (line, column) = line_and_column(source, error.span().map_or(0, |span| span.start));
+            SceneError::Parse { path: path.to_path_buf(), line, column, message: error.message().to_string() }
+        })?;
+
+        let mut loader = Loader {
+            path,
+            textures: HashMap::new(),
+            materials: HashMap::new(),
+            emissive: HashSet::new(),
+            material_ids: HashMap::new(),
+            shape_descriptions: &description.shapes,
+            shapes: HashMap::new(),
+            warnings: Vec::new()
+        };
+        for (name, texture) in &description.textures {
+            let texture = loader.build_texture(name, texture)?;
+            loader.textures.insert(name.clone(), texture);
+        }
+        for (name, material_description) in &description.materials {
+            let material = loader.build_material(name, material_description)?;
+            loader.materials.insert(name.clone(), material);
+            if let MaterialDescription::DiffuseLight { .. } = material_description {
+                loader.emissive.insert(name.clone());
+            }
+        }
+        let mut material_names: Vec<&String> = description.materials.keys().collect();
+        material_names.sort();
+        loader.material_ids = material_names.into_iter().enumerate()
+            .map(|(index, name)| (name.clone(), index + 1))
+            .collect();
+
+        // Shapes are all built before any are added, so they can't place each other.
+        let mut shapes = HashMap::new();
+        for (name, shape) in &description.shapes {
+            let shape: Arc<dyn Hittable> = Arc::from(loader.build_object(&format!("shapes.{}", name), shape)?);
+            shapes.insert(name.clone(), shape);
+        }
+        loader.shapes = shapes;
+
+        if description.objects.is_empty() {
+            return Err(loader.error("objects", "scene has no objects".to_string()));
+        }
+
+        let camera = loader.camera(&description.camera)?;
+
+        let mut world = HittableList::new();
+        let mut lights = HittableList::new();
+        for (index, object) in description.objects.iter().enumerate() {
+            let context = format!("objects[{}]", index);
+            let built: Arc<dyn Hittable> = Arc::from(loader.build_object(&context, object)?);
+            if loader.is_light(object) {
+                lights.add(Arc::clone(&built));
+            } else if loader.is_emissive(object) {
+                let message = "meshes and instances aren't sampled as lights, so its light is only found by chance";
+                loader.warnings.push(loader.error(&context, message.to_string()).to_string());
+            }
+            // Objects are numbered from 1 in file order, for the object ID AOV.
+            world.add(Tagged::from(Box::new(built), index + 1, loader.material_id(object)));
+        }
+
+        Ok(Scene { camera, world, lights, warnings: loader.warnings })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::random::Prng;
+    use crate::common::ray::Ray;
+    use crate::common::vec3::Point3;
+
+    use super::*;
+
+    const SPHERE: &str = r#"
+        [materials.grey]
+        type = "lambertian"
+        albedo = [0.5, 0.5, 0.5]
+
+        [[objects]]
+        type = "sphere"
+        center = [0.0, 0.0, -1.0]
+        radius = 0.5
+        material = "grey"
+    "#;
+
+    fn invalid_context(source: &str) -> String {
+        match Scene::parse(source, Path::new("test.toml")) {
+            Err(SceneError::Invalid { context, .. }) => context,
+            Err(error) => panic!("expected an invalid scene, got {}", error),
+            Ok(_) => panic!("expected an invalid scene, but it loaded")
+        }
+    }
+
+    #[test]
+    fn loads_a_valid_scene() {
+        let scene = Scene::parse(SPHERE, Path::new("test.toml")).expect("scene is valid");
+        assert!(!scene.world.is_empty());
+    }
+
+    #[test]
+    fn rejects_empty_or_negative_camera_sizes() {
+        for (field, value) in [("image_width", "0"), ("samples_per_pixel", "-4"), ("max_depth", "0"), ("aspect_ratio", "-1.5"), ("adaptive_threshold", "-0.01")] {
+            let source = format!("[camera]\n{} = {}\n{}", field, value, SPHERE);
+            assert_eq!(invalid_context(&source), format!("camera.{}", field));
+        }
+    }
+
+    #[test]
+    fn rejects_empty_or_negative_objects() {
+        assert_eq!(invalid_context(&SPHERE.replace("radius = 0.5", "radius = 0.0")), "objects[0].radius");
+
+        let medium = format!("{}\n{}", SPHERE, r#"
+            [[objects]]
+            type = "constant_medium"
+            density = -0.5
+            material = "grey"
+            boundary = { type = "sphere", center = [0.0, 0.0, 0.0], radius = 1.0, material = "grey" }
+        "#);
+        assert_eq!(invalid_context(&medium), "objects[1].density");
+
+        let checker = format!("{}\n{}", SPHERE, r#"
+            [textures.floor]
+            type = "checker"
+            scale = 0.0
+            even = [0.0, 0.0, 0.0]
+            odd = [1.0, 1.0, 1.0]
+        "#);
+        assert_eq!(invalid_context(&checker), "textures.floor.scale");
+    }
+
+    #[test]
+    fn emissive_triangles_and_boxes_are_sampled_as_lights() {
+        let source = format!("{}\n{}", SPHERE, r#"
+            [materials.lamp]
+            type = "diffuse_light"
+            emit = [4.0, 4.0, 4.0]
+
+            [[objects]]
+            type = "triangle"
+            a = [-1.0, 2.0, -1.0]
+            b = [1.0, 2.0, -1.0]
+            c = [0.0, 2.0, 1.0]
+            material = "lamp"
+
+            [[objects]]
+            type = "box"
+            a = [3.0, -1.0, -1.0]
+            b = [4.0, 1.0, 1.0]
+            material = "lamp"
+        "#);
+        let scene = Scene::parse(&source, Path::new("test.toml")).expect("scene is valid");
+
+        let mut rng = Prng::seed(0);
+        let origin = Point3::new();
+        for target in [Point3::build(0.0, 2.0, -0.5), Point3::build(3.0, 0.0, 0.0)] {
+            let ray = Ray::from(origin, target - origin);
+            assert!(scene.lights.pdf_value(&ray, &mut rng) > 0.0, "the light towards {:?} isn't sampled", target);
+        }
+    }
+
+    #[test]
+    fn emissive_instances_are_reported_as_warnings() {
+        let source = format!("{}\n{}", SPHERE, r#"
+            [materials.lamp]
+            type = "diffuse_light"
+            emit = [4.0, 4.0, 4.0]
+
+            [shapes.bulb]
+            type = "sphere"
+            center = [0.0, 0.0, 0.0]
+            radius = 0.5
+            material = "lamp"
+
+            [[objects]]
+            type = "instance"
+            shape = "bulb"
+            translate = [0.0, 2.0, 0.0]
+        "#);
+        let scene = Scene::parse(&source, Path::new("test.toml")).expect("scene is valid");
+        assert_eq!(scene.warnings.len(), 1);
+        assert!(scene.warnings[0].starts_with("test.toml: objects[1]: "), "{}", scene.warnings[0]);
+
+        let plain = Scene::parse(SPHERE, Path::new("test.toml")).expect("scene is valid");
+        assert!(plain.warnings.is_empty());
+    }
+
+    #[test]
+    fn unreadable_material_libraries_are_reported_as_warnings() {
+        let directory = std::env::temp_dir().join(format!("rtow_rust_scene_mtllib_{}", std::process::id()));
+        fs::create_dir_all(&directory).expect("temporary directory is writable");
+        fs::write(directory.join("mesh.obj"), "mtllib missing.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n")
+            .expect("temporary file is writable");
+
+        let source = format!("{}\n{}", SPHERE, r#"
+            [[objects]]
+            type = "mesh"
+            path = "mesh.obj"
+        "#);
+        let scene = Scene::parse(&source, &directory.join("test.toml"));
+        fs::remove_dir_all(&directory).ok();
+
+        let scene = scene.expect("a missing mtllib isn't an error");
+        assert_eq!(scene.warnings.len(), 1);
+        assert!(scene.warnings[0].contains("objects[1]: ") && scene.warnings[0].contains("missing.mtl"), "{}", scene.warnings[0]);
+    }
+}
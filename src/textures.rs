@@ -0,0 +1,21 @@
+use std::sync::Arc;
+
+use crate::common::{ colour::Colour, vec3::Point3 };
+
+pub mod solid_colour;
+pub mod checker;
+pub mod image_texture;
+pub mod noise_texture;
+
+// Like materials, textures are shared between materials and across render threads.
+pub type TexArc = Arc<Box<dyn Texture>>;
+
+pub trait Texture: Send + Sync {
+    // Colour at the surface coordinates (u, v) of a hit at `point`.
+    fn value(&self, u: f64, v: f64, point: & Point3) -> Colour;
+}
+
+// Wraps a constant colour, for materials given a colour rather than a texture.
+pub fn solid_texture(colour: Colour) -> TexArc {
+    Arc::new(Box::new(solid_colour::SolidColour::from(colour)))
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use crate::common::colour::Colour;
+use crate::common::vec3::Point3;
+
+use super::{ solid_texture, TexArc, Texture };
+
+// Alternates between two textures in cubes of side `scale` through space, so
+// the pattern doesn't depend on the surface coordinates.
+pub struct Checker {
+    inverse_scale: f64,
+    even: TexArc,
+    odd: TexArc
+}
+
+impl Checker {
+    pub fn build(scale: f64, even: & TexArc, odd: & TexArc) -> Checker {
+        Checker::from(scale, Arc::clone(even), Arc::clone(odd))
+    }
+
+    pub fn build_colours(scale: f64, even: Colour, odd: Colour) -> Checker {
+        Checker::from(scale, solid_texture(even), solid_texture(odd))
+    }
+
+    pub fn from(scale: f64, even: TexArc, odd: TexArc) -> Checker {
+        Checker {
+            inverse_scale: 1.0 / scale,
+            even,
+            odd
+        }
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f64, v: f64, point: & Point3) -> Colour {
+        let x = (self.inverse_scale * point.x()).floor() as i64;
+        let y = (self.inverse_scale * point.y()).floor() as i64;
+        let z = (self.inverse_scale * point.z()).floor() as i64;
+
+        if (x + y + z).rem_euclid(2) == 0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+}
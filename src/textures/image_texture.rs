@@ -0,0 +1,226 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{ Path, PathBuf };
+
+use crate::common::colour::Colour;
+use crate::common::vec3::Point3;
+
+use super::Texture;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+const JPEG_SIGNATURE: [u8; 2] = [0xff, 0xd8];
+
+// What happens to texture coordinates outside [0, 1].
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum WrapMode {
+    Repeat,
+    // Repeats, flipping every other copy so edges line up.
+    Mirror,
+    // Stretches the edge texels outwards.
+    Clamp
+}
+
+#[derive(Debug)]
+pub enum TextureError {
+    Io(PathBuf, io::Error),
+    Decode(PathBuf, String),
+    UnknownFormat(PathBuf)
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextureError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            TextureError::Decode(path, message) => write!(f, "{}: {}", path.display(), message),
+            TextureError::UnknownFormat(path) => write!(f, "{}: not a PNG or JPEG image", path.display())
+        }
+    }
+}
+
+impl std::error::Error for TextureError {}
+
+// A bilinearly filtered image, with (0, 0) at the bottom left corner and
+// (1, 1) at the top right.
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    // Linear colour, row by row from the top.
+    pixels: Vec<Colour>,
+    wrap: WrapMode
+}
+
+impl ImageTexture {
+    // `pixels` holds width * height linear colours, row by row from the top.
+    pub fn from(width: usize, height: usize, pixels: Vec<Colour>, wrap: WrapMode) -> ImageTexture {
+        assert!(width > 0 && height > 0, "ImageTexture: image has no pixels.");
+        assert_eq!(pixels.len(), width * height, "ImageTexture: pixel count doesn't match the size.");
+
+        ImageTexture { width, height, pixels, wrap }
+    }
+
+    // Loads a PNG or JPEG file, told apart by their signatures rather than the extension.
+    pub fn load(path: & Path, wrap: WrapMode) -> Result<ImageTexture, TextureError> {
+        let bytes = fs::read(path).map_err(|error| TextureError::Io(path.to_path_buf(), error))?;
+
+        let (width, height, rgb) = if bytes.starts_with(&PNG_SIGNATURE) {
+            decode_png(&bytes)
+        } else if bytes.starts_with(&JPEG_SIGNATURE) {
+            decode_jpeg(&bytes)
+        } else {
+            return Err(TextureError::UnknownFormat(path.to_path_buf()))
+        }.map_err(|message| TextureError::Decode(path.to_path_buf(), message))?;
+
+        if width == 0 || height == 0 {
+            return Err(TextureError::Decode(path.to_path_buf(), "image has no pixels".to_string()))
+        }
+
+        let pixels = rgb.chunks_exact(3)
+            .map(|texel| Colour::build(gamma_to_linear(texel[0]), gamma_to_linear(texel[1]), gamma_to_linear(texel[2])))
+            .collect();
+
+        Ok(ImageTexture::from(width, height, pixels, wrap))
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Colour {
+        let x = wrap_index(x, self.width, self.wrap);
+        let y = wrap_index(y, self.height, self.wrap);
+        self.pixels[y * self.width + x]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _point: & Point3) -> Colour {
+        // Texel centres sit at half-integer positions; flip v since rows start at the top.
+        let x = u * self.width as f64 - 0.5;
+        let y = (1.0 - v) * self.height as f64 - 0.5;
+
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let tx = x - x0;
+        let ty = y - y0;
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0) * (1.0 - tx) + self.texel(x0 + 1, y0) * tx;
+        let bottom = self.texel(x0, y0 + 1) * (1.0 - tx) + self.texel(x0 + 1, y0 + 1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
+
+fn wrap_index(index: i64, size: usize, wrap: WrapMode) -> usize {
+    let size = size as i64;
+    let wrapped = match wrap {
+        WrapMode::Repeat => index.rem_euclid(size),
+        WrapMode::Mirror => {
+            let period = index.rem_euclid(2 * size);
+            if period < size { period } else { 2 * size - 1 - period }
+        }
+        WrapMode::Clamp => index.clamp(0, size - 1)
+    };
+    wrapped as usize
+}
+
+// Inverse of the gamma 2 encoding images are written with.
+fn gamma_to_linear(byte: u8) -> f64 {
+    let encoded = byte as f64 / 255.0;
+    encoded * encoded
+}
+
+// Width, height and 8-bit RGB triples, whatever the colour type of the file.
+fn decode_png(bytes: & [u8]) -> Result<(usize, usize, Vec<u8>), String> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|error| error.to_string())?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer).map_err(|error| error.to_string())?;
+    let data = &buffer[..frame.buffer_size()];
+
+    let rgb = match frame.color_type {
+        png::ColorType::Rgb => data.to_vec(),
+        png::ColorType::Rgba => data.chunks_exact(4).flat_map(|texel| [texel[0], texel[1], texel[2]]).collect(),
+        png::ColorType::Grayscale => data.iter().flat_map(|&grey| [grey; 3]).collect(),
+        png::ColorType::GrayscaleAlpha => data.chunks_exact(2).flat_map(|texel| [texel[0]; 3]).collect(),
+        png::ColorType::Indexed => return Err("palette was not expanded".to_string())
+    };
+
+    Ok((frame.width as usize, frame.height as usize, rgb))
+}
+
+fn decode_jpeg(bytes: & [u8]) -> Result<(usize, usize, Vec<u8>), String> {
+    let mut decoder = jpeg_decoder::Decoder::new(bytes);
+    let data = decoder.decode().map_err(|error| error.to_string())?;
+    let info = decoder.info().ok_or("missing image header")?;
+
+    let rgb = match info.pixel_format {
+        jpeg_decoder::PixelFormat::RGB24 => data,
+        jpeg_decoder::PixelFormat::L8 => data.iter().flat_map(|&grey| [grey; 3]).collect(),
+        // Big-endian 16-bit samples; keep the high byte.
+        jpeg_decoder::PixelFormat::L16 => data.chunks_exact(2).flat_map(|sample| [sample[0]; 3]).collect(),
+        jpeg_decoder::PixelFormat::CMYK32 => return Err("CMYK JPEGs are not supported".to_string())
+    };
+
+    Ok((info.width as usize, info.height as usize, rgb))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    fn grey(value: f64) -> Colour {
+        Colour::build(value, value, value)
+    }
+
+    // 0 and 1 along the top row, 2 and 3 along the bottom.
+    fn texture(wrap: WrapMode) -> ImageTexture {
+        ImageTexture::from(2, 2, vec![grey(0.0), grey(1.0), grey(2.0), grey(3.0)], wrap)
+    }
+
+    fn value_at(texture: & ImageTexture, u: f64, v: f64) -> f64 {
+        let colour = texture.value(u, v, &Point3::new());
+        assert!(colour.x() == colour.y() && colour.y() == colour.z());
+        colour.x()
+    }
+
+    #[test]
+    fn texel_centres_are_exact_and_between_them_is_blended() {
+        let texture = texture(WrapMode::Clamp);
+        assert_eq!(value_at(&texture, 0.25, 0.75), 0.0);
+        assert_eq!(value_at(&texture, 0.75, 0.75), 1.0);
+        assert_eq!(value_at(&texture, 0.25, 0.25), 2.0);
+        assert_eq!(value_at(&texture, 0.75, 0.25), 3.0);
+
+        assert_eq!(value_at(&texture, 0.5, 0.5), 1.5);
+        assert_eq!(value_at(&texture, 0.5, 0.75), 0.5);
+    }
+
+    // Along the top row, at the centres of the texels just outside the image.
+    #[test]
+    fn wrap_modes_decide_what_lies_outside() {
+        let cases = [
+            (WrapMode::Repeat, [1.0, 0.0, 0.0]),
+            (WrapMode::Mirror, [0.0, 1.0, 1.0]),
+            (WrapMode::Clamp, [0.0, 1.0, 0.0])
+        ];
+        for (wrap, [left, right, two_left]) in cases {
+            let texture = texture(wrap);
+            assert_eq!(value_at(&texture, -0.25, 0.75), left, "{:?} at u = -0.25", wrap);
+            assert_eq!(value_at(&texture, 1.25, 0.75), right, "{:?} at u = 1.25", wrap);
+            assert_eq!(value_at(&texture, -0.75, 0.75), two_left, "{:?} at u = -0.75", wrap);
+        }
+    }
+
+    #[test]
+    fn load_rejects_files_that_are_not_images() {
+        let path = env::temp_dir().join(format!("rtow_rust_texture_{}.png", process::id()));
+        fs::write(&path, b"GIF89a, not a PNG").expect("temporary file is writable");
+        let result = ImageTexture::load(&path, WrapMode::Repeat);
+        fs::remove_file(&path).ok();
+
+        assert!(matches!(result, Err(TextureError::UnknownFormat(_))));
+    }
+}
@@ -0,0 +1,79 @@
+use crate::common::colour::Colour;
+use crate::common::noise::{ fbm, ridged, turbulence, Noise, Perlin, Worley, DEFAULT_GAIN, DEFAULT_LACUNARITY };
+use crate::common::vec3::Point3;
+
+use super::Texture;
+
+// Octaves summed for the fractal patterns.
+const FRACTAL_OCTAVES: usize = 7;
+
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum NoisePattern {
+    // Plain Perlin noise, mapped from [-1, 1] to [0, 1].
+    Smooth,
+    // Several octaves of |noise|, like a net of creases.
+    Turbulence,
+    // Stripes along z, bent by turbulence.
+    Marble,
+    // Rings around the y axis, wobbled by fBm.
+    Wood,
+    // Soft fBm billows.
+    Clouds,
+    // Sharp ridged multifractal crests, like mountain ranges seen from above.
+    Ridged,
+    // Worley cells, dark at their centres.
+    Cells
+}
+
+// Modulates a colour by a noise pattern, sampled at the hit point scaled by `scale`.
+pub struct NoiseTexture {
+    perlin: Perlin,
+    worley: Worley,
+    scale: f64,
+    colour: Colour,
+    pattern: NoisePattern
+}
+
+impl NoiseTexture {
+    pub fn from(scale: f64, colour: Colour, pattern: NoisePattern, seed: u64) -> NoiseTexture {
+        NoiseTexture {
+            perlin: Perlin::seed(seed),
+            worley: Worley::seed(seed),
+            scale,
+            colour,
+            pattern
+        }
+    }
+
+    fn turbulence(&self, point: & Point3) -> f64 {
+        turbulence(&self.perlin, point, FRACTAL_OCTAVES, DEFAULT_LACUNARITY, DEFAULT_GAIN)
+    }
+
+    fn fbm(&self, point: & Point3) -> f64 {
+        fbm(&self.perlin, point, FRACTAL_OCTAVES, DEFAULT_LACUNARITY, DEFAULT_GAIN)
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, point: & Point3) -> Colour {
+        let scaled = *point * self.scale;
+
+        let intensity = match self.pattern {
+            NoisePattern::Smooth => 0.5 * (1.0 + self.perlin.noise(&scaled)),
+            NoisePattern::Turbulence => self.turbulence(&scaled),
+            NoisePattern::Marble => 0.5 * (1.0 + f64::sin(scaled.z() + 10.0 * self.turbulence(point))),
+            NoisePattern::Wood => {
+                let radius = f64::sqrt(scaled.x() * scaled.x() + scaled.z() * scaled.z());
+                let ring = (radius + 0.5 * self.fbm(&scaled)).rem_euclid(1.0);
+                // Light early wood fading into a darker late band.
+                0.5 + 0.5 * ring * ring
+            }
+            NoisePattern::Clouds => 0.5 * (1.0 + self.fbm(&scaled)),
+            NoisePattern::Ridged => 0.5 * ridged(&self.perlin, &scaled, FRACTAL_OCTAVES, DEFAULT_LACUNARITY, DEFAULT_GAIN),
+            NoisePattern::Cells => self.worley.noise(&scaled)
+        };
+
+        self.colour * intensity.clamp(0.0, 1.0)
+    }
+}
@@ -0,0 +1,46 @@
+use crate::common::colour::Colour;
+use crate::common::vec3::Point3;
+
+use super::Texture;
+
+pub struct SolidColour {
+    albedo: Colour
+}
+
+impl SolidColour {
+    pub fn new() -> SolidColour {
+        SolidColour {
+            albedo: Colour::build(0.5, 0.5, 0.5)
+        }
+    }
+
+    pub fn build(colour: & Colour) -> SolidColour {
+        SolidColour {
+            albedo: *colour
+        }
+    }
+
+    pub fn build_explicit(r: f64, g: f64, b: f64) -> SolidColour {
+        SolidColour {
+            albedo: Colour::build(r, g, b)
+        }
+    }
+
+    pub fn from(colour: Colour) -> SolidColour {
+        SolidColour {
+            albedo: colour
+        }
+    }
+}
+
+impl Default for SolidColour {
+    fn default() -> SolidColour {
+        SolidColour::new()
+    }
+}
+
+impl Texture for SolidColour {
+    fn value(&self, _u: f64, _v: f64, _point: & Point3) -> Colour {
+        self.albedo
+    }
+}